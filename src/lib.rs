@@ -0,0 +1,5002 @@
+//! Renders grouped bar charts as SVG from criterion benchmark results.
+
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Debug;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::str::FromStr;
+
+use svg::node::element::{self, Group as SVGGroup};
+use svg::node::element::{Line, Rectangle, Title};
+use svg::{Document, Node};
+
+#[derive(Clone)]
+pub struct BenchData {
+    pub bench_name: String,
+    pub group_name: String,
+    pub variant: String,
+    pub num_bytes: u32,
+    pub gbs: f64,
+    /// throughput computed from the median duration
+    pub median_gbs: Option<f64>,
+    pub bar_label: Option<String>,
+    /// label of the group, instead of the humanized number of bytes
+    pub group_label: Option<String>,
+    /// total number of iterations over all samples
+    pub iterations: Option<u64>,
+    pub samples: Option<usize>,
+    /// the throughput at the 5th, 25th, 50th, 75th and 95th percentile
+    pub percentiles: Option<[f64; 5]>,
+    /// coefficient of variation of the duration per iteration over all samples
+    pub cv: Option<f64>,
+    /// further values by name, `time_ns` and the fields of [`LoadOptions::metric_fields`]
+    pub metrics: BTreeMap<String, f64>,
+}
+impl Debug for BenchData {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BenchData")
+            .field("bench_name", &self.bench_name)
+            .field("group_name", &self.group_name)
+            .field("variant", &self.variant)
+            .field("num_bytes", &self.num_bytes)
+            .field("gbs", &self.gbs)
+            .field("median_gbs", &self.median_gbs)
+            .field("bar_label", &self.bar_label)
+            .field("group_label", &self.group_label)
+            .field("iterations", &self.iterations)
+            .field("samples", &self.samples)
+            .field("percentiles", &self.percentiles)
+            .field("cv", &self.cv)
+            .field("metrics", &self.metrics)
+            .finish()
+    }
+}
+
+#[derive(Debug)]
+pub struct LoadOptions {
+    /// field path of the text label of a bar
+    pub bar_label_field: Option<String>,
+    /// field path of the label of the group, instead of the humanized number of bytes
+    pub group_label_field: Option<String>,
+    /// field path of the number of bytes, instead of the third component of the id
+    pub bytes_field: Option<String>,
+    /// field paths of the durations at the 5th, 25th, 50th, 75th and 95th percentile
+    pub percentile_fields: Option<[String; 5]>,
+    /// the `reason` of the lines to load, `*` loads all lines
+    pub reason_filter: String,
+    /// load lines without a `reason` instead of skipping them
+    pub accept_missing_reason: bool,
+    /// stop after loading this many benchmarks
+    pub max_rows: Option<usize>,
+    /// only load benchmarks with an id matching one of these patterns, all if empty. See
+    /// [`glob_match`]
+    pub id_allowlist: Vec<String>,
+    /// skip benchmarks with an id matching one of these patterns
+    pub id_denylist: Vec<String>,
+    /// field paths of further numbers to load into [`BenchData::metrics`]
+    pub metric_fields: Vec<String>,
+}
+impl Default for LoadOptions {
+    fn default() -> Self {
+        LoadOptions {
+            bar_label_field: None,
+            group_label_field: None,
+            bytes_field: None,
+            percentile_fields: None,
+            reason_filter: "benchmark-complete".to_string(),
+            accept_missing_reason: false,
+            max_rows: None,
+            id_allowlist: vec![],
+            id_denylist: vec![],
+            metric_fields: vec![],
+        }
+    }
+}
+
+/// Looks up a dot separated field path like `meta.commit` or `throughput.0.unit`.
+pub fn lookup_field<'a>(val: &'a serde_json::Value, path: &str) -> Option<&'a serde_json::Value> {
+    path.split('.').try_fold(val, |val, key| match val {
+        serde_json::Value::Array(arr) => arr.get(key.parse::<usize>().ok()?),
+        _ => val.get(key),
+    })
+}
+
+/// Looks up a field path as text, strings without quotes. Empty texts are `None`.
+fn field_text(val: &serde_json::Value, path: &str) -> Option<String> {
+    let text = match lookup_field(val, path)? {
+        serde_json::Value::String(text) => text.to_string(),
+        text => text.to_string(),
+    };
+    Some(text).filter(|text| !text.is_empty())
+}
+
+/// A warning raised while loading or rendering.
+#[derive(Debug, Clone)]
+pub struct Warning {
+    pub kind: &'static str,
+    pub message: String,
+    /// 1-based line of the input the warning refers to
+    pub line: Option<usize>,
+}
+
+/// Collects warnings, logging each one as it is added.
+#[derive(Debug, Default)]
+pub struct Warnings {
+    pub entries: Vec<Warning>,
+}
+impl Warnings {
+    pub fn add(&mut self, kind: &'static str, message: String, line: Option<usize>) {
+        match line {
+            Some(line) => log::warn!("line {}: {}", line, message),
+            None => log::warn!("{}", message),
+        }
+        self.entries.push(Warning {
+            kind,
+            message,
+            line,
+        });
+    }
+
+    /// Returns the warnings as JSON array of `{kind, message, line}` objects.
+    pub fn to_json(&self) -> serde_json::Value {
+        self.entries
+            .iter()
+            .map(|warning| {
+                serde_json::json!({
+                    "kind": warning.kind,
+                    "message": warning.message,
+                    "line": warning.line,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Loads criterion's JSON message output. Lines which can't be parsed are skipped with a
+/// warning.
+pub fn load_data(
+    file_name: &str,
+    load_options: &LoadOptions,
+    warnings: &mut Warnings,
+) -> BTreeMap<String, Vec<BenchData>> {
+    let mut groups = BTreeMap::new();
+    //let mut data = Vec::new();
+    //let file = File::open("./data.json").unwrap();
+    let file = File::open(file_name).unwrap();
+    let mut num_rows = 0;
+    for (line_idx, line) in BufReader::new(file).lines().enumerate() {
+        let line_no = Some(line_idx + 1);
+        let line = line.unwrap();
+        if line.trim().is_empty() {
+            continue;
+        }
+        let val: serde_json::Value = match serde_json::from_str(&line) {
+            Ok(val) => val,
+            Err(err) => {
+                warnings.add(
+                    "invalid_json",
+                    format!("skipping invalid JSON ({})", err),
+                    line_no,
+                );
+                continue;
+            }
+        };
+        match val["reason"].as_str() {
+            Some(reason) => {
+                if load_options.reason_filter != "*" && reason != load_options.reason_filter {
+                    log::debug!("skipping line with reason {:?}", reason);
+                    continue;
+                }
+            }
+            None if load_options.accept_missing_reason => {}
+            None => {
+                warnings.add(
+                    "missing_field",
+                    "skipping line without reason".to_string(),
+                    line_no,
+                );
+                continue;
+            }
+        }
+        let (Some(name), Some(duration_ns)) =
+            (val["id"].as_str(), val["typical"]["estimate"].as_f64())
+        else {
+            warnings.add(
+                "missing_field",
+                "skipping benchmark without id or typical.estimate".to_string(),
+                line_no,
+            );
+            continue;
+        };
+        let allowed = load_options.id_allowlist.is_empty()
+            || load_options
+                .id_allowlist
+                .iter()
+                .any(|pattern| glob_match(pattern, name));
+        let denied = load_options
+            .id_denylist
+            .iter()
+            .any(|pattern| glob_match(pattern, name));
+        if !allowed || denied {
+            log::debug!("skipping {} by the id allowlist or denylist", name);
+            continue;
+        }
+        // only count the lines which would be loaded, e.g. not the `group-complete` lines
+        if load_options.max_rows == Some(num_rows) {
+            warnings.add(
+                "max_rows",
+                format!("stopped after {} benchmarks, ignoring the rest", num_rows),
+                line_no,
+            );
+            break;
+        }
+        let name = name.to_string();
+        let components: Vec<String> = name.split("/").map(|el| el.to_string()).collect();
+        let num_bytes = match &load_options.bytes_field {
+            Some(path) => {
+                let num_bytes = lookup_field(&val, path).and_then(|num_bytes| match num_bytes {
+                    serde_json::Value::String(num_bytes) => num_bytes.parse::<u32>().ok(),
+                    num_bytes => num_bytes
+                        .as_u64()
+                        .and_then(|num_bytes| u32::try_from(num_bytes).ok()),
+                });
+                let Some(num_bytes) = num_bytes else {
+                    warnings.add(
+                        "missing_field",
+                        format!(
+                            "skipping {}, {} is missing or not a number below 4 GiB",
+                            name, path
+                        ),
+                        line_no,
+                    );
+                    continue;
+                };
+                num_bytes
+            }
+            None => match components.get(2).map(|num_bytes| num_bytes.parse::<u32>()) {
+                Some(Ok(num_bytes)) => num_bytes,
+                _ => {
+                    warnings.add(
+                        "invalid_id",
+                        format!(
+                            "skipping {}, expected an id like <bench>/<variant>/<num_bytes>",
+                            name
+                        ),
+                        line_no,
+                    );
+                    continue;
+                }
+            },
+        };
+        let [bench_name, variant, ..] = components.as_slice() else {
+            warnings.add(
+                "invalid_id",
+                format!("skipping {}, expected an id like <bench>/<variant>", name),
+                line_no,
+            );
+            continue;
+        };
+        let bench_name = bench_name.to_string();
+        let variant = variant.to_string();
+
+        let group_name = format!("{}/{}", bench_name, num_bytes);
+
+        let gbs = num_bytes as f64 / duration_ns;
+        let median_gbs = val["median"]["estimate"]
+            .as_f64()
+            .map(|median_ns| num_bytes as f64 / median_ns);
+        //data.push((bench_name, group_name, variant, num_bytes, gbs));
+        if num_bytes == 96274 {
+            log::debug!("skipping {}", name);
+            continue;
+        }
+
+        let bar_label = load_options
+            .bar_label_field
+            .as_ref()
+            .and_then(|path| field_text(&val, path));
+        let group_label = load_options
+            .group_label_field
+            .as_ref()
+            .and_then(|path| field_text(&val, path));
+
+        let (iterations, samples) = iteration_stats(&val["iteration_count"]);
+        let percentiles = load_options.percentile_fields.as_ref().and_then(|paths| {
+            let mut percentiles = [0.0; 5];
+            for (percentile, path) in percentiles.iter_mut().zip(paths) {
+                *percentile = num_bytes as f64 / lookup_field(&val, path)?.as_f64()?;
+            }
+            // the shortest duration is the highest throughput
+            percentiles.reverse();
+            Some(percentiles)
+        });
+        if load_options.percentile_fields.is_some() && percentiles.is_none() {
+            log::debug!("{} has no percentiles, drawing a bar", name);
+        }
+
+        let mut metrics = BTreeMap::from([("time_ns".to_string(), duration_ns)]);
+        for path in &load_options.metric_fields {
+            if let Some(value) = lookup_field(&val, path).and_then(|value| value.as_f64()) {
+                metrics.insert(path.to_string(), value);
+            }
+        }
+
+        let blub: &mut Vec<_> = groups.entry(group_name.to_string()).or_default();
+
+        blub.push(BenchData {
+            bench_name,
+            group_name,
+            variant,
+            num_bytes,
+            gbs,
+            median_gbs,
+            bar_label,
+            group_label,
+            iterations,
+            samples,
+            percentiles,
+            cv: coefficient_of_variation(&val["iteration_count"], &val["measured_values"]),
+            metrics,
+        });
+        num_rows += 1;
+    }
+    log::debug!("loaded groups from {}: {:#?}", file_name, groups);
+    groups
+}
+
+/// Loads the benchmarks of two criterion output directories (e.g. `target/criterion`) for
+/// comparison. Every benchmark becomes a group with an `old` and a `new` bar; benchmarks
+/// present in only one directory get a single bar and a warning.
+pub fn load_criterion_dirs(
+    old_dir: &str,
+    new_dir: &str,
+    warnings: &mut Warnings,
+) -> BTreeMap<String, Vec<BenchData>> {
+    let old = load_criterion_dir(old_dir, "old", warnings);
+    let mut new = load_criterion_dir(new_dir, "new", warnings);
+
+    let mut groups: BTreeMap<String, Vec<BenchData>> = BTreeMap::new();
+    for (id, old_bench) in old {
+        let new_bench = new.remove(&id);
+        if new_bench.is_none() {
+            warnings.add(
+                "missing_benchmark",
+                format!("{} is only in {}", id, old_dir),
+                None,
+            );
+        }
+        groups
+            .entry(id)
+            .or_default()
+            .extend(std::iter::once(old_bench).chain(new_bench));
+    }
+    for (id, new_bench) in new {
+        warnings.add(
+            "missing_benchmark",
+            format!("{} is only in {}", id, new_dir),
+            None,
+        );
+        groups.entry(id).or_default().push(new_bench);
+    }
+    groups
+}
+
+/// Walks a criterion output directory and loads the `new` estimates of every benchmark, keyed
+/// by the benchmark id.
+fn load_criterion_dir(
+    dir: &str,
+    variant: &str,
+    warnings: &mut Warnings,
+) -> BTreeMap<String, BenchData> {
+    let mut benches = BTreeMap::new();
+    let mut pending = vec![std::path::PathBuf::from(dir)];
+    while let Some(dir) = pending.pop() {
+        let entries = std::fs::read_dir(&dir).unwrap();
+        for path in entries.map(|entry| entry.unwrap().path()) {
+            // criterion's html reports live in `report` directories
+            if !path.is_dir() || path.file_name().is_some_and(|name| name == "report") {
+                continue;
+            }
+            if path.file_name().is_some_and(|name| name == "new") {
+                if let Some(bench) = load_criterion_benchmark(&path, variant, warnings) {
+                    benches.insert(bench.group_name.to_string(), bench);
+                }
+            } else {
+                pending.push(path);
+            }
+        }
+    }
+    benches
+}
+
+/// Loads `benchmark.json` and `estimates.json` of a benchmark's `new` directory.
+fn load_criterion_benchmark(
+    dir: &Path,
+    variant: &str,
+    warnings: &mut Warnings,
+) -> Option<BenchData> {
+    let read_json = |file_name: &str| -> Option<serde_json::Value> {
+        let file = File::open(dir.join(file_name)).ok()?;
+        serde_json::from_reader(BufReader::new(file)).ok()
+    };
+    let (Some(benchmark), Some(estimates)) =
+        (read_json("benchmark.json"), read_json("estimates.json"))
+    else {
+        warnings.add(
+            "missing_field",
+            format!(
+                "skipping {}, missing benchmark.json or estimates.json",
+                dir.display()
+            ),
+            None,
+        );
+        return None;
+    };
+
+    let id = benchmark["full_id"].as_str()?.to_string();
+    let num_bytes = match benchmark["throughput"]["Bytes"].as_u64() {
+        Some(num_bytes) => u32::try_from(num_bytes).ok(),
+        None => benchmark["value_str"].as_str().and_then(|v| v.parse().ok()),
+    };
+    let Some(num_bytes) = num_bytes else {
+        warnings.add(
+            "missing_field",
+            format!(
+                "skipping {}, the throughput is missing or not a number below 4 GiB",
+                id
+            ),
+            None,
+        );
+        return None;
+    };
+    // criterion reports the slope as typical estimate if available
+    let duration_ns = estimates["slope"]["point_estimate"]
+        .as_f64()
+        .or_else(|| estimates["mean"]["point_estimate"].as_f64());
+    let Some(duration_ns) = duration_ns else {
+        warnings.add(
+            "missing_field",
+            format!("skipping {}, missing estimate", id),
+            None,
+        );
+        return None;
+    };
+
+    let sample = read_json("sample.json");
+    let (iterations, samples) = sample
+        .as_ref()
+        .map(|sample| iteration_stats(&sample["iters"]))
+        .unwrap_or_default();
+    let cv = sample.and_then(|sample| coefficient_of_variation(&sample["iters"], &sample["times"]));
+    let function_id = benchmark["function_id"].as_str().unwrap_or_default();
+    let value_str = benchmark["value_str"].as_str().unwrap_or_default();
+    Some(BenchData {
+        bench_name: benchmark["group_id"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string(),
+        group_name: id,
+        variant: variant.to_string(),
+        num_bytes,
+        gbs: num_bytes as f64 / duration_ns,
+        median_gbs: estimates["median"]["point_estimate"]
+            .as_f64()
+            .map(|median_ns| num_bytes as f64 / median_ns),
+        bar_label: None,
+        group_label: Some(if value_str.is_empty() {
+            function_id.to_string()
+        } else {
+            format!("{} {}", function_id, value_str)
+        }),
+        iterations,
+        samples,
+        percentiles: None,
+        cv,
+        metrics: BTreeMap::from([("time_ns".to_string(), duration_ns)]),
+    })
+}
+
+/// The standard deviation divided by the mean of the duration per iteration of the samples,
+/// from the arrays of iteration counts and measured durations per sample.
+fn coefficient_of_variation(
+    iteration_count: &serde_json::Value,
+    measured_values: &serde_json::Value,
+) -> Option<f64> {
+    let per_iteration: Vec<f64> = iteration_count
+        .as_array()?
+        .iter()
+        .zip(measured_values.as_array()?)
+        .filter_map(|(count, value)| Some(value.as_f64()? / count.as_f64()?))
+        .collect();
+    if per_iteration.len() < 2 {
+        return None;
+    }
+    let mean = Stat::Mean.apply(&per_iteration);
+    let variance = per_iteration
+        .iter()
+        .map(|value| (value - mean).powi(2))
+        .sum::<f64>()
+        / (per_iteration.len() - 1) as f64;
+    Some(variance.sqrt() / mean)
+}
+
+/// Returns the total number of iterations and the number of samples of an array of
+/// iteration counts per sample.
+fn iteration_stats(iteration_count: &serde_json::Value) -> (Option<u64>, Option<usize>) {
+    match iteration_count.as_array() {
+        Some(counts) => (
+            Some(
+                counts
+                    .iter()
+                    .filter_map(|count| count.as_f64())
+                    .sum::<f64>() as u64,
+            ),
+            Some(counts.len()),
+        ),
+        None => (None, None),
+    }
+}
+
+/// Presentation defaults describing a dataset.
+#[derive(Debug, Default)]
+pub struct Metadata {
+    pub title: Option<String>,
+    pub y_label: Option<String>,
+    /// group name or number of bytes to label
+    pub labels: BTreeMap<String, String>,
+}
+
+/// Loads the metadata file. `unit` is used as y axis label when `y_label` is missing.
+pub fn load_metadata(file_name: &str) -> Metadata {
+    let file = File::open(file_name).unwrap();
+    let val: serde_json::Value = serde_json::from_reader(BufReader::new(file)).unwrap();
+    let get_str = |key: &str| val[key].as_str().map(|el| el.to_string());
+    let labels = val["labels"]
+        .as_object()
+        .map(|labels| {
+            labels
+                .iter()
+                .filter_map(|(key, label)| Some((key.to_string(), label.as_str()?.to_string())))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let meta = Metadata {
+        title: get_str("title"),
+        y_label: get_str("y_label").or_else(|| get_str("unit")),
+        labels,
+    };
+    log::debug!("loaded metadata from {}: {:?}", file_name, meta);
+    meta
+}
+
+/// Loads the values of the overlay line, a JSON object of group label to value.
+pub fn load_overlay_line(file_name: &str) -> BTreeMap<String, f64> {
+    let file = File::open(file_name).unwrap();
+    let val: serde_json::Value = serde_json::from_reader(BufReader::new(file)).unwrap();
+    val.as_object()
+        .map(|values| {
+            values
+                .iter()
+                .filter_map(|(label, value)| Some((label.to_string(), value.as_f64()?)))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Bucketing {
+    /// [2^k, 2^(k+1))
+    Pow2,
+    /// [n * step, (n + 1) * step)
+    Linear(u32),
+}
+impl Bucketing {
+    /// Returns the inclusive start and exclusive end of the bucket of `num_bytes`.
+    pub fn bucket(&self, num_bytes: u32) -> (u64, u64) {
+        match self {
+            Bucketing::Pow2 if num_bytes == 0 => (0, 1),
+            Bucketing::Pow2 => {
+                let start = 1u64 << (31 - num_bytes.leading_zeros());
+                (start, start * 2)
+            }
+            Bucketing::Linear(step) => {
+                let step = *step as u64;
+                let start = num_bytes as u64 / step * step;
+                (start, start + step)
+            }
+        }
+    }
+}
+impl FromStr for Bucketing {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "pow2" {
+            return Ok(Bucketing::Pow2);
+        }
+        match s.strip_prefix("linear:").map(|step| step.parse::<u32>()) {
+            Some(Ok(step)) if step > 0 => Ok(Bucketing::Linear(step)),
+            _ => Err(format!(
+                "invalid bucketing {:?}, expected pow2 or linear:<step>",
+                s
+            )),
+        }
+    }
+}
+
+/// How the numbers of the y axis ticks are printed.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum AxisNumberFormat {
+    /// the shortest representation of the value
+    #[default]
+    Auto,
+    /// with a fixed number of decimals
+    Fixed(usize),
+    /// e.g. `1.2e6`
+    Scientific,
+    /// with an SI suffix, e.g. `1.5M`
+    Compact,
+}
+impl AxisNumberFormat {
+    pub fn format(&self, value: f32) -> String {
+        match self {
+            AxisNumberFormat::Auto => value.to_string(),
+            AxisNumberFormat::Fixed(decimals) => format!("{:.*}", decimals, value),
+            AxisNumberFormat::Scientific => format!("{:.1e}", value),
+            AxisNumberFormat::Compact => compact_number(value),
+        }
+    }
+}
+
+/// Prints large numbers with an SI suffix and without trailing zeros, e.g. `1500000` as
+/// `1.5M`.
+pub fn compact_number(value: f32) -> String {
+    let suffixes = [(1e12, "T"), (1e9, "G"), (1e6, "M"), (1e3, "k")];
+    let Some(idx) = suffixes.iter().position(|(scale, _)| value.abs() >= *scale) else {
+        return value.to_string();
+    };
+    // 999999 rounds to 1000k with two decimals, print it as 1M instead
+    let rounds_up = (value.abs() / suffixes[idx].0 * 100.0).round() >= 100_000.0;
+    let idx = match idx.checked_sub(1) {
+        Some(larger) if rounds_up => larger,
+        _ => idx,
+    };
+    let (scale, suffix) = suffixes[idx];
+    let number = format!("{:.2}", value / scale);
+    let number = number.trim_end_matches('0').trim_end_matches('.');
+    format!("{}{}", number, suffix)
+}
+impl FromStr for AxisNumberFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => return Ok(AxisNumberFormat::Auto),
+            "scientific" => return Ok(AxisNumberFormat::Scientific),
+            "compact" => return Ok(AxisNumberFormat::Compact),
+            _ => {}
+        }
+        match s
+            .strip_prefix("fixed:")
+            .map(|decimals| decimals.parse::<usize>())
+        {
+            Some(Ok(decimals)) => Ok(AxisNumberFormat::Fixed(decimals)),
+            _ => Err(format!(
+                "invalid axis number format {:?}, expected auto, fixed:<decimals>, scientific or compact",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Stat {
+    Mean,
+    Median,
+    Min,
+    Max,
+    Sum,
+}
+impl Stat {
+    pub fn apply(&self, values: &[f64]) -> f64 {
+        match self {
+            Stat::Mean => values.iter().sum::<f64>() / values.len() as f64,
+            Stat::Median => {
+                let mut values = values.to_vec();
+                values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+                let mid = values.len() / 2;
+                if values.len().is_multiple_of(2) {
+                    (values[mid - 1] + values[mid]) / 2.0
+                } else {
+                    values[mid]
+                }
+            }
+            Stat::Min => values.iter().cloned().fold(f64::INFINITY, f64::min),
+            Stat::Max => values.iter().cloned().fold(f64::NEG_INFINITY, f64::max),
+            Stat::Sum => values.iter().sum(),
+        }
+    }
+}
+impl FromStr for Stat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "mean" => Ok(Stat::Mean),
+            "median" => Ok(Stat::Median),
+            "min" => Ok(Stat::Min),
+            "max" => Ok(Stat::Max),
+            "sum" => Ok(Stat::Sum),
+            _ => Err(format!(
+                "unknown stat {:?}, expected mean, median, min, max or sum",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum DataLabels {
+    Inside,
+    Above,
+    #[default]
+    None,
+}
+impl FromStr for DataLabels {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "inside" => Ok(DataLabels::Inside),
+            "above" => Ok(DataLabels::Above),
+            "none" => Ok(DataLabels::None),
+            _ => Err(format!(
+                "unknown data label position {:?}, expected inside, above or none",
+                s
+            )),
+        }
+    }
+}
+
+/// Where the min-max delta of a group is printed.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum DeltaPosition {
+    /// centered above the highest bar of the group
+    #[default]
+    AboveMax,
+    /// centered above the whole group, just above its highest bar
+    AboveGroup,
+    /// centered above the whole group, at the top of the plot area
+    Top,
+}
+impl FromStr for DeltaPosition {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "above-max" => Ok(DeltaPosition::AboveMax),
+            "above-group" => Ok(DeltaPosition::AboveGroup),
+            "top" => Ok(DeltaPosition::Top),
+            _ => Err(format!(
+                "unknown delta position {:?}, expected above-max, above-group or top",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ChartType {
+    #[default]
+    Bar,
+    /// box and whisker plots of the percentiles, bars without percentiles are drawn as
+    /// usual
+    Box,
+}
+impl FromStr for ChartType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bar" => Ok(ChartType::Bar),
+            "box" => Ok(ChartType::Box),
+            _ => Err(format!("unknown chart type {:?}, expected bar or box", s)),
+        }
+    }
+}
+
+/// How colors are assigned to variants.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ColorMode {
+    /// by the sorted position of the variant
+    #[default]
+    Ordered,
+    /// by a hash of the variant name, so a variant keeps its color across datasets unless
+    /// it collides with another variant
+    Hash,
+}
+impl FromStr for ColorMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ordered" => Ok(ColorMode::Ordered),
+            "hash" => Ok(ColorMode::Hash),
+            _ => Err(format!(
+                "unknown color mode {:?}, expected ordered or hash",
+                s
+            )),
+        }
+    }
+}
+
+/// Order of the legend entries, by variant name or by the mean value of the variant.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum LegendSort {
+    #[default]
+    Name,
+    ValueAsc,
+    ValueDesc,
+}
+impl FromStr for LegendSort {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "name" => Ok(LegendSort::Name),
+            "value-asc" => Ok(LegendSort::ValueAsc),
+            "value-desc" => Ok(LegendSort::ValueDesc),
+            _ => Err(format!(
+                "unknown legend sort {:?}, expected name, value-asc or value-desc",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ByteUnit {
+    #[default]
+    Si,
+    Binary,
+}
+impl FromStr for ByteUnit {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "si" => Ok(ByteUnit::Si),
+            "binary" => Ok(ByteUnit::Binary),
+            _ => Err(format!("unknown byte unit {:?}, expected si or binary", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum DuplicateLabels {
+    #[default]
+    Bench,
+    Counter,
+    Warn,
+}
+impl FromStr for DuplicateLabels {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bench" => Ok(DuplicateLabels::Bench),
+            "counter" => Ok(DuplicateLabels::Counter),
+            "warn" => Ok(DuplicateLabels::Warn),
+            _ => Err(format!(
+                "unknown duplicate label mode {:?}, expected bench, counter or warn",
+                s
+            )),
+        }
+    }
+}
+
+/// How group and legend labels are built from the bench data.
+#[derive(Debug, Clone, Default)]
+pub struct LabelOptions {
+    /// group name or number of bytes to group label
+    pub overrides: BTreeMap<String, String>,
+    pub byte_unit: ByteUnit,
+    pub duplicate_labels: DuplicateLabels,
+    /// append the min-max range of each variant to its legend entry
+    pub legend_range: bool,
+    /// add the number of samples and iterations to the tooltip of each bar
+    pub rich_tooltips: bool,
+    pub legend_sort: LegendSort,
+    /// order of the legend entries, unlisted variants follow in alphabetical order
+    pub variant_order: Vec<String>,
+    /// group the legend by the family of the variants, see [`variant_family`]
+    pub family_separator: Option<char>,
+    /// order groups with the same name prefix by their trailing number, highest first
+    pub group_numeric_desc: bool,
+    /// name of the group created by [`merge_into_other`], drawn in a neutral color
+    pub other_group: Option<String>,
+}
+
+/// A chart with its groups, the color of each variant and the options to render it with.
+#[derive(Debug, Clone)]
+pub struct Chart {
+    pub title: String,
+    pub groups: Vec<Group>,
+    /// label and color of every legend entry, headers have no color
+    pub legend: Vec<(String, String)>,
+    pub variant_to_color: BTreeMap<String, String>,
+    pub options: GroupBarOptions,
+}
+
+impl Chart {
+    pub fn new(title: &str, options: GroupBarOptions) -> Self {
+        Chart {
+            title: title.to_string(),
+            groups: vec![],
+            legend: vec![],
+            variant_to_color: BTreeMap::new(),
+            options,
+        }
+    }
+
+    /// Loads a chart from criterion's JSON message output with the default options.
+    pub fn from_criterion(file_name: &str) -> Self {
+        let mut warnings = Warnings::default();
+        let name_to_benches = load_data(file_name, &LoadOptions::default(), &mut warnings);
+        let variant_to_color = build_variant_to_color(&name_to_benches, ColorMode::Ordered);
+        build_chart(
+            "",
+            &name_to_benches,
+            &variant_to_color,
+            &LabelOptions::default(),
+            GroupBarOptions::default(),
+            &mut warnings,
+        )
+    }
+
+    /// Adds a group with a bar per `(variant, value)`. Variants without a color yet get the
+    /// next palette color and a legend entry.
+    pub fn add_group(&mut self, label: &str, values: &[(&str, f32)]) {
+        let bars = values
+            .iter()
+            .map(|(variant, value)| {
+                if !self.variant_to_color.contains_key(*variant) {
+                    let color = palette_color(self.variant_to_color.len());
+                    self.variant_to_color
+                        .insert(variant.to_string(), color.to_string());
+                    self.legend
+                        .push((self.options.abbreviate(variant), color.to_string()));
+                }
+                Bar {
+                    variant: variant.to_string(),
+                    value: *value,
+                    color: self.variant_to_color[*variant].to_string(),
+                    label: None,
+                    median: None,
+                    tooltip: Some(format!("{}: {:.2}", variant, value)),
+                    percentiles: None,
+                    cv: None,
+                }
+            })
+            .collect();
+        self.groups.push(Group {
+            label: label.to_string(),
+            bench_name: String::new(),
+            bars,
+            expected: None,
+        });
+    }
+
+    pub fn render(&self) -> Document {
+        self.render_with_hook(|_, _| {})
+    }
+
+    /// Renders the chart and lets `hook` append custom elements, see
+    /// [`render_grouped_bar_chart_with_hook`].
+    pub fn render_with_hook(&self, hook: impl FnMut(&mut SVGGroup, &ChartLayout)) -> Document {
+        let opt = &self.options;
+        let (width, height) = (opt.total_width, opt.get_document_height());
+        let document = new_root_group(width, height, opt.background_gradient.as_ref());
+        let document = render_grouped_bar_chart_with_hook(
+            &self.title,
+            document,
+            opt.clone(),
+            &self.groups,
+            &self.legend,
+            hook,
+        );
+
+        finish_document(document, width, height, opt.rotate)
+    }
+
+    /// The group label, variant and change in percent of every bar flagged by
+    /// [`bar_regression`].
+    pub fn regressions(&self) -> Vec<(String, String, f32)> {
+        self.groups
+            .iter()
+            .flat_map(|group| {
+                group.bars.iter().filter_map(|bar| {
+                    let change = bar_regression(&self.options, group, bar)?;
+                    Some((group.label.to_string(), bar.variant.to_string(), change))
+                })
+            })
+            .collect()
+    }
+
+    /// Colors every bar by its value relative to the highest bar, on a ramp from `cool` to
+    /// `warm`. The legend is replaced by stops of the ramp.
+    pub fn apply_heat(&mut self, cool: &str, warm: &str) {
+        let max_value = self
+            .groups
+            .iter()
+            .flat_map(|group| &group.bars)
+            .map(|bar| bar.value)
+            .fold(0.0, f32::max);
+        if max_value <= 0.0 {
+            return;
+        }
+        for bar in self.groups.iter_mut().flat_map(|group| &mut group.bars) {
+            bar.color = heat_color(cool, warm, bar.value / max_value);
+        }
+        let num_stops = 5;
+        self.legend = (0..num_stops)
+            .rev()
+            .map(|stop| {
+                let ratio = stop as f32 / (num_stops - 1) as f32;
+                (
+                    format!("{:.2}", max_value * ratio),
+                    heat_color(cool, warm, ratio),
+                )
+            })
+            .collect();
+    }
+
+    /// Sets the value of the overlay line of every group with a label in `values`.
+    pub fn set_overlay_line(&mut self, values: &BTreeMap<String, f64>) {
+        for group in self.groups.iter_mut() {
+            group.expected = values.get(&group.label).map(|value| *value as f32);
+        }
+    }
+
+    pub fn save(&self, file_name: &str) -> std::io::Result<()> {
+        svg::save(file_name, &self.render())
+    }
+}
+
+/// Renders the chart as text with a line of block characters per bar, for a terminal with
+/// `width` columns.
+pub fn render_preview(chart: &Chart, width: usize) -> String {
+    let bars = chart.groups.iter().flat_map(|group| &group.bars);
+    let max_value = bars.clone().map(|bar| bar.value).fold(0.0, f32::max);
+    let variant_width = bars
+        .clone()
+        .map(|bar| bar.variant.chars().count())
+        .max()
+        .unwrap_or(0);
+    let value_width = format!("{:.2}", max_value).len();
+    // "  <variant> |<bar> <value>"
+    let max_blocks = width.saturating_sub(variant_width + value_width + 5).max(1);
+
+    let mut preview = String::new();
+    if !chart.title.is_empty() {
+        preview += &format!("{}\n", chart.title);
+    }
+    for group in &chart.groups {
+        preview += &format!("{}\n", group.label);
+        for bar in &group.bars {
+            let blocks = if max_value > 0.0 {
+                (bar.value / max_value * max_blocks as f32).round() as usize
+            } else {
+                0
+            };
+            preview += &format!(
+                "  {:<variant_width$} |{} {:.2}\n",
+                bar.variant,
+                "█".repeat(blocks),
+                bar.value,
+            );
+        }
+    }
+    preview
+}
+
+/// Interpolates linearly between two `#RRGGBB` colors, `ratio` 0 is `cool` and 1 is `warm`.
+pub fn heat_color(cool: &str, warm: &str, ratio: f32) -> String {
+    let channel = |color: &str, idx: usize| {
+        color
+            .trim_start_matches('#')
+            .get(idx..idx + 2)
+            .and_then(|c| u8::from_str_radix(c, 16).ok())
+            .unwrap_or(0) as f32
+    };
+    let ratio = ratio.clamp(0.0, 1.0);
+    let mix = |idx: usize| {
+        let (cool, warm) = (channel(cool, idx), channel(warm, idx));
+        (cool + (warm - cool) * ratio).round() as u8
+    };
+    format!("#{:02X}{:02X}{:02X}", mix(0), mix(2), mix(4))
+}
+
+/// The highest bar or overlay line value of the groups. Box plots reach up to the 95th
+/// percentile instead.
+pub fn max_chart_value(groups: &[Group], chart_type: ChartType) -> f32 {
+    groups
+        .iter()
+        .flat_map(|g| {
+            g.bars
+                .iter()
+                .map(move |bar| match (chart_type, bar.percentiles) {
+                    (ChartType::Box, Some(percentiles)) => percentiles[4],
+                    _ => bar.value,
+                })
+                .chain(g.expected)
+        })
+        .max_by(|a, b| a.partial_cmp(b).unwrap())
+        .unwrap()
+}
+
+/// Presentation attributes moved into the style block by [`move_styles_to_css`].
+const STYLE_ATTRIBUTES: [&str; 9] = [
+    "fill",
+    "stroke",
+    "stroke-width",
+    "stroke-opacity",
+    "stroke-dasharray",
+    "font-family",
+    "font-size",
+    "font-weight",
+    "text-anchor",
+];
+
+/// Moves the presentation attributes of all elements of a serialized SVG document into a
+/// `<style>` block, with a class per distinct combination of attributes. Only geometry is
+/// left as attributes, so the chart can be restyled with CSS.
+pub fn move_styles_to_css(svg: &str) -> String {
+    let mut classes: Vec<String> = vec![];
+    let mut out = String::with_capacity(svg.len());
+    let mut rest = svg;
+    while let Some(start) = rest.find('<') {
+        out += &rest[..start];
+        rest = &rest[start..];
+        let is_element = rest[1..].starts_with(|c: char| c.is_ascii_alphabetic());
+        let Some(tag) = parse_tag(rest).filter(|_| is_element) else {
+            out.push('<');
+            rest = &rest[1..];
+            continue;
+        };
+        // the fill of an animation is not a color
+        let (style, geometry): (Vec<_>, Vec<_>) =
+            tag.attributes.into_iter().partition(|(key, _)| {
+                tag.name != "animate" && STYLE_ATTRIBUTES.contains(&key.as_str())
+            });
+        out += &format!("<{}", tag.name);
+        if !style.is_empty() {
+            let declarations: String = style
+                .iter()
+                .map(|(key, value)| {
+                    let is_length = matches!(key.as_str(), "stroke-width" | "font-size");
+                    match value.parse::<f32>() {
+                        Ok(_) if is_length => format!("{}: {}px; ", key, value),
+                        _ => format!("{}: {}; ", key, value),
+                    }
+                })
+                .collect();
+            let idx = match classes.iter().position(|class| *class == declarations) {
+                Some(idx) => idx,
+                None => {
+                    classes.push(declarations);
+                    classes.len() - 1
+                }
+            };
+            out += &format!(" class=\"s{}\"", idx);
+        }
+        for (key, value) in geometry {
+            out += &format!(" {}=\"{}\"", key, value);
+        }
+        out += tag.end;
+        rest = &rest[tag.len..];
+    }
+    out += rest;
+
+    let style: String = classes
+        .iter()
+        .enumerate()
+        .map(|(idx, declarations)| format!(".s{} {{ {}}}\n", idx, declarations))
+        .collect();
+    // directly after the opening svg tag
+    match out
+        .find("<svg")
+        .and_then(|start| Some(start + out[start..].find('>')? + 1))
+    {
+        Some(pos) => format!("{}\n<style>\n{}</style>{}", &out[..pos], style, &out[pos..]),
+        None => out,
+    }
+}
+
+/// An opening tag of a serialized element.
+struct Tag {
+    name: String,
+    attributes: Vec<(String, String)>,
+    /// `>` or `/>`
+    end: &'static str,
+    /// length of the tag in the text
+    len: usize,
+}
+
+/// Parses the opening tag at the start of `text`.
+fn parse_tag(text: &str) -> Option<Tag> {
+    let name_len = text[1..].find(|c: char| c.is_whitespace() || c == '>' || c == '/')?;
+    let name = text[1..1 + name_len].to_string();
+    let mut rest = text[1 + name_len..].trim_start();
+    let mut attributes = vec![];
+    loop {
+        for end in ["/>", ">"] {
+            if rest.starts_with(end) {
+                return Some(Tag {
+                    name,
+                    attributes,
+                    end,
+                    len: text.len() - rest.len() + end.len(),
+                });
+            }
+        }
+        let (key, after_key) = rest.split_once('=')?;
+        let quote = after_key
+            .chars()
+            .next()
+            .filter(|c| *c == '"' || *c == '\'')?;
+        let (value, after_value) = after_key[1..].split_once(quote)?;
+        attributes.push((key.trim().to_string(), value.to_string()));
+        rest = after_value.trim_start();
+    }
+}
+
+/// Embeds the rendered document into a standalone HTML page. With `a11y_table` every chart
+/// is followed by a visually hidden table of its values for screen readers.
+/// With `image_map`, the SVG is embedded as an image with an image map of the bars instead of
+/// inline. Several charts are expected to be stacked like by [`render_panels`].
+pub fn render_html(
+    title: &str,
+    svg: &str,
+    charts: &[Chart],
+    a11y_table: bool,
+    image_map: bool,
+) -> String {
+    let mut html = String::new();
+    html += "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n";
+    html += &format!("<title>{}</title>\n", escape_html(title));
+    if a11y_table {
+        html += "<style>.visually-hidden { position: absolute; width: 1px; height: 1px; \
+                 overflow: hidden; clip: rect(0 0 0 0); white-space: nowrap; }</style>\n";
+    }
+    html += "</head>\n<body>\n";
+    if image_map {
+        html += &format!(
+            "<img src=\"data:image/svg+xml,{}\" alt=\"{}\" usemap=\"#chart-map\">\n",
+            percent_encode(svg),
+            escape_html(title)
+        );
+        html += "<map name=\"chart-map\">\n";
+        let panel_height = tile_height(charts);
+        for (idx, chart) in charts.iter().enumerate() {
+            html += &bar_image_map(chart, idx as f32 * panel_height);
+        }
+        html += "</map>\n";
+    } else {
+        html += svg;
+        html += "\n";
+    }
+    if a11y_table {
+        for chart in charts {
+            html += &data_table(chart);
+        }
+    }
+    html += "</body>\n</html>\n";
+    html
+}
+
+/// A table with a row per group and a column per variant.
+fn data_table(chart: &Chart) -> String {
+    let mut variants: Vec<&str> = vec![];
+    for bar in chart.groups.iter().flat_map(|group| &group.bars) {
+        if !variants.contains(&bar.variant.as_str()) {
+            variants.push(&bar.variant);
+        }
+    }
+    let mut table = String::from("<table class=\"visually-hidden\">\n");
+    table += &format!("<caption>{}</caption>\n", escape_html(&chart.title));
+    table += "<tr><th scope=\"col\">Group</th>";
+    for variant in &variants {
+        table += &format!("<th scope=\"col\">{}</th>", escape_html(variant));
+    }
+    table += "</tr>\n";
+    for group in &chart.groups {
+        table += &format!("<tr><th scope=\"row\">{}</th>", escape_html(&group.label));
+        for variant in &variants {
+            match group.bars.iter().find(|bar| bar.variant == *variant) {
+                Some(bar) => table += &format!("<td>{:.2}</td>", bar.value),
+                None => table += "<td></td>",
+            }
+        }
+        table += "</tr>\n";
+    }
+    table += "</table>\n";
+    table
+}
+
+/// An `<area>` covering every bar of a chart drawn `y_offset` below the top, with the group,
+/// variant and value as `data-*` attributes.
+fn bar_image_map(chart: &Chart, y_offset: f32) -> String {
+    let layout = ChartLayout::new(&chart.options, &chart.groups);
+    let bottom = y_offset + layout.plot_y + layout.plot_height;
+    let mut map = String::new();
+    for (group_idx, group) in chart.groups.iter().enumerate() {
+        for (bar_idx, bar) in group.bars.iter().enumerate() {
+            // the same edges as the drawn bar
+            let (x, width) = bar_edges(
+                layout.bar_x(group_idx, bar_idx),
+                layout.bar_width,
+                chart.options.snap_bars,
+            );
+            let y = y_offset + layout.y_for_value(bar.value).max(layout.plot_y);
+            let tooltip = bar
+                .tooltip
+                .clone()
+                .unwrap_or_else(|| format!("{}: {:.2}", bar.variant, bar.value));
+            map += &format!(
+                "<area shape=\"rect\" coords=\"{},{},{},{}\" href=\"#\" title=\"{}\" \
+                 data-group=\"{}\" data-variant=\"{}\" data-value=\"{}\">\n",
+                x.round(),
+                y.round(),
+                (x + width).round(),
+                bottom.round(),
+                escape_html(&tooltip),
+                escape_html(&group.label),
+                escape_html(&bar.variant),
+                bar.value
+            );
+        }
+    }
+    map
+}
+
+/// Percent-encodes everything but ASCII letters, digits and `-_.~`, for data URIs.
+fn percent_encode(text: &str) -> String {
+    let mut encoded = String::with_capacity(text.len());
+    for byte in text.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-_.~".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded += &format!("%{:02X}", byte);
+        }
+    }
+    encoded
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Builds a chart from the benches, using the colors of `variant_to_color`.
+pub fn build_chart(
+    title: &str,
+    name_to_benches: &BTreeMap<String, Vec<BenchData>>,
+    variant_to_color: &BTreeMap<String, String>,
+    label_options: &LabelOptions,
+    options: GroupBarOptions,
+    warnings: &mut Warnings,
+) -> Chart {
+    let variant_ranges = variant_ranges(name_to_benches);
+    let mut variants: Vec<(&String, &String)> = variant_to_color
+        .iter()
+        .filter(|(variant, _)| variant_ranges.contains_key(*variant))
+        .collect();
+    if !label_options.variant_order.is_empty() {
+        variants.sort_by_key(|(variant, _)| variant_rank(&label_options.variant_order, variant));
+    }
+    if label_options.legend_sort != LegendSort::Name {
+        let means = variant_means(name_to_benches);
+        variants.sort_by(|(a, _), (b, _)| means[*a].partial_cmp(&means[*b]).unwrap());
+        if label_options.legend_sort == LegendSort::ValueDesc {
+            variants.reverse();
+        }
+    }
+    // abbreviate before appending the range, which must stay readable
+    let legend_label = |variant: &str, name: &str| {
+        let name = options.abbreviate(name);
+        if label_options.legend_range {
+            let (min, max) = variant_ranges[variant];
+            format!("{} [{:.2}–{:.2}]", name, min, max)
+        } else {
+            name
+        }
+    };
+    let legend: Vec<(String, String)> = match label_options.family_separator {
+        Some(separator) => {
+            // a header per family in the order of its first member, followed by the members
+            // without the family prefix. A family of one is a plain entry
+            let mut families: Vec<(&str, Vec<(&String, &String)>)> = vec![];
+            for (variant, color) in variants {
+                let family = variant_family(variant, separator);
+                match families.iter_mut().find(|(name, _)| *name == family) {
+                    Some((_, members)) => members.push((variant, color)),
+                    None => families.push((family, vec![(variant, color)])),
+                }
+            }
+            let mut legend = vec![];
+            for (family, members) in families {
+                if let [(variant, color)] = members[..] {
+                    legend.push((legend_label(variant, variant), color.to_string()));
+                    continue;
+                }
+                legend.push((options.abbreviate(family), String::new()));
+                for (variant, color) in members {
+                    let name = variant
+                        .strip_prefix(family)
+                        .and_then(|name| name.strip_prefix(separator))
+                        .filter(|name| !name.is_empty())
+                        .unwrap_or(variant);
+                    legend.push((legend_label(variant, name), color.to_string()));
+                }
+            }
+            legend
+        }
+        None => variants
+            .into_iter()
+            .map(|(variant, color)| (legend_label(variant, variant), color.to_string()))
+            .collect(),
+    };
+
+    let mut groups = vec![];
+
+    // sort before building the labels, so that the counters of duplicates follow the order
+    let mut sorted_groups: Vec<_> = name_to_benches.iter().collect();
+    sorted_groups
+        .sort_by(|(a, _), (b, _)| compare_group_names(a, b, label_options.group_numeric_desc));
+    let labels = group_labels(
+        &sorted_groups,
+        &label_options.overrides,
+        label_options.byte_unit,
+        label_options.duplicate_labels,
+        warnings,
+    );
+    for ((name, group), label) in sorted_groups.into_iter().zip(labels) {
+        let is_other = label_options.other_group.as_ref() == Some(name);
+        let bars = group
+            .iter()
+            .map(|run| Bar {
+                variant: run.variant.to_string(),
+                value: run.gbs as f32,
+                color: if is_other {
+                    OTHER_GROUP_COLOR.to_string()
+                } else {
+                    variant_to_color.get(&run.variant).unwrap().to_string()
+                },
+                label: run.bar_label.clone(),
+                median: run.median_gbs.map(|median| median as f32),
+                tooltip: Some(bar_tooltip(run, &label, label_options.rich_tooltips)),
+                percentiles: run
+                    .percentiles
+                    .map(|percentiles| percentiles.map(|percentile| percentile as f32)),
+                cv: run.cv.map(|cv| cv as f32),
+            })
+            .collect();
+        let gruppe = Group {
+            label,
+            bench_name: group
+                .first()
+                .map(|bench| bench.bench_name.to_string())
+                .unwrap_or_default(),
+            bars,
+            expected: None,
+        };
+        groups.push(gruppe);
+    }
+
+    Chart {
+        title: title.to_string(),
+        groups,
+        legend,
+        variant_to_color: variant_to_color.clone(),
+        options,
+    }
+}
+
+fn bar_tooltip(bench: &BenchData, group_label: &str, rich: bool) -> String {
+    let mut tooltip = format!("{} {}: {:.2}", bench.variant, group_label, bench.gbs);
+    if rich {
+        if let Some(cv) = bench.cv {
+            tooltip += &format!("\nCV {:.1}%", cv * 100.0);
+        }
+        if let Some(samples) = bench.samples {
+            tooltip += &format!("\n{} samples", samples);
+        }
+        if let Some(iterations) = bench.iterations {
+            tooltip += &format!("\n{} iterations", iterations);
+        }
+    }
+    tooltip
+}
+
+/// Orders group names by their prefix and then by their trailing number, so `bench/2` comes
+/// before `bench/1000`.
+pub fn compare_group_names(a: &str, b: &str, numeric_desc: bool) -> Ordering {
+    fn split(name: &str) -> (&str, &str) {
+        let prefix = name.trim_end_matches(|c: char| c.is_ascii_digit());
+        let number = name[prefix.len()..].trim_start_matches('0');
+        (prefix, number)
+    }
+    let ((a_prefix, a_number), (b_prefix, b_number)) = (split(a), split(b));
+    let numeric = a_number
+        .len()
+        .cmp(&b_number.len())
+        .then_with(|| a_number.cmp(b_number));
+    let numeric = if numeric_desc {
+        numeric.reverse()
+    } else {
+        numeric
+    };
+    a_prefix.cmp(b_prefix).then(numeric).then_with(|| a.cmp(b))
+}
+
+/// Splits the groups by their bench name.
+pub fn split_by_bench(
+    name_to_benches: &BTreeMap<String, Vec<BenchData>>,
+) -> BTreeMap<String, BTreeMap<String, Vec<BenchData>>> {
+    let mut benches: BTreeMap<String, BTreeMap<String, Vec<BenchData>>> = BTreeMap::new();
+    for (name, group) in name_to_benches {
+        benches
+            .entry(group[0].bench_name.to_string())
+            .or_default()
+            .insert(name.to_string(), group.clone());
+    }
+    benches
+}
+
+/// Renders the charts stacked on top of each other, as panels sharing the x positions of the
+/// groups. The charts should have the same groups and width.
+pub fn render_panels(charts: &[Chart]) -> Document {
+    render_contact_sheet(charts, 1)
+}
+
+/// Renders every chart as a tile into a grid with `columns` columns. The tile width and
+/// rotation are taken from the options of the first chart, the tile height fits the highest
+/// chart, e.g. one with a delta chart.
+pub fn render_contact_sheet(charts: &[Chart], columns: u32) -> Document {
+    let opt = &charts[0].options;
+    let columns = (columns.max(1) as usize).min(charts.len());
+    let rows = charts.len().div_ceil(columns);
+    let tile_height = tile_height(charts);
+    let tile_width = opt.total_width;
+    let (width, height) = (tile_width * columns as f32, tile_height * rows as f32);
+
+    let mut document = new_root_group(width, height, opt.background_gradient.as_ref());
+    for (idx, chart) in charts.iter().enumerate() {
+        let x = (idx % columns) as f32 * tile_width;
+        let y = (idx / columns) as f32 * tile_height;
+        let tile = element::Group::new().set("transform", format!("translate({},{})", x, y));
+        let tile = render_grouped_bar_chart(
+            &chart.title,
+            tile,
+            chart.options.clone(),
+            &chart.groups,
+            &chart.legend,
+        );
+        document = document.add(tile);
+    }
+
+    finish_document(document, width, height, opt.rotate)
+}
+
+/// The height of a tile of [`render_contact_sheet`], which fits the highest chart.
+fn tile_height(charts: &[Chart]) -> f32 {
+    charts
+        .iter()
+        .map(|chart| chart.options.get_document_height())
+        .fold(0.0, f32::max)
+}
+
+/// The root group with the font settings and the background, a vertical gradient from the
+/// first to the second color if `gradient` is set.
+fn new_root_group(width: f32, height: f32, gradient: Option<&(String, String)>) -> SVGGroup {
+    let mut document = element::Group::new();
+    document = document.set("font-family", "Roboto-Regular,Roboto, sans-serif");
+    document = document.set("fill", "#FFFFFF");
+    let mut rect = Rectangle::new()
+        .set("width", width)
+        .set("height", height)
+        .set("fill", "#333333");
+    if let Some((top, bottom)) = gradient {
+        let linear_gradient = element::LinearGradient::new()
+            .set("id", BACKGROUND_GRADIENT_ID)
+            .set("x1", 0)
+            .set("y1", 0)
+            .set("x2", 0)
+            .set("y2", 1)
+            .add(
+                element::Stop::new()
+                    .set("offset", 0)
+                    .set("stop-color", top.to_string()),
+            )
+            .add(
+                element::Stop::new()
+                    .set("offset", 1)
+                    .set("stop-color", bottom.to_string()),
+            );
+        document = document.add(element::Definitions::new().add(linear_gradient));
+        rect = rect.set("fill", format!("url(#{})", BACKGROUND_GRADIENT_ID));
+    }
+
+    document.add(rect)
+}
+
+/// Wraps the root group of a `width` x `height` chart into the document, rotating it by
+/// `rotate` degrees. For 90 and 270 the document dimensions are swapped.
+fn finish_document(mut root: SVGGroup, width: f32, height: f32, rotate: u32) -> Document {
+    let (doc_width, doc_height) = match rotate {
+        90 => {
+            root = root.set("transform", format!("translate({},0) rotate(90)", height));
+            (height, width)
+        }
+        270 => {
+            root = root.set("transform", format!("translate(0,{}) rotate(270)", width));
+            (height, width)
+        }
+        _ => (width, height),
+    };
+
+    Document::new()
+        .set("width", doc_width)
+        .set("height", doc_height)
+        .set("viewBox", (0, 0, doc_width, doc_height))
+        .add(root)
+}
+
+/// Assigns a color to every variant present in `name_to_benches`.
+pub fn build_variant_to_color(
+    name_to_benches: &BTreeMap<String, Vec<BenchData>>,
+    color_mode: ColorMode,
+) -> BTreeMap<String, String> {
+    let variants = name_to_benches
+        .iter()
+        .flat_map(|group| group.1.iter())
+        .map(|b| b.variant.to_string())
+        .collect::<BTreeSet<_>>();
+
+    match color_mode {
+        ColorMode::Ordered => variants
+            .iter()
+            .enumerate()
+            .map(|(idx, variant)| (variant.to_string(), palette_color(idx).to_string()))
+            .collect(),
+        ColorMode::Hash => {
+            let mut used = [false; PALETTE.len()];
+            variants
+                .iter()
+                .map(|variant| {
+                    let mut idx = fnv1a(variant) as usize % PALETTE.len();
+                    // on a collision take the next free color, while there is one
+                    if used.contains(&false) {
+                        while used[idx] {
+                            idx = (idx + 1) % PALETTE.len();
+                        }
+                    }
+                    used[idx] = true;
+                    (variant.to_string(), palette_color(idx).to_string())
+                })
+                .collect()
+        }
+    }
+}
+
+/// The family of a variant, the part of its name before the first `separator`, e.g. `zstd` for
+/// `zstd-19` with `-`.
+pub fn variant_family(variant: &str, separator: char) -> &str {
+    variant.split(separator).next().unwrap_or(variant)
+}
+
+/// Colors the variants by their family, see [`variant_family`]. Every family gets its own hue
+/// and the members of a family different shades of it, from dark to light.
+pub fn build_family_colors(
+    name_to_benches: &BTreeMap<String, Vec<BenchData>>,
+    separator: char,
+) -> BTreeMap<String, String> {
+    let variants = name_to_benches
+        .iter()
+        .flat_map(|group| group.1.iter())
+        .map(|b| b.variant.to_string())
+        .collect::<BTreeSet<_>>();
+    let mut families: BTreeMap<&str, Vec<&String>> = BTreeMap::new();
+    for variant in &variants {
+        families
+            .entry(variant_family(variant, separator))
+            .or_default()
+            .push(variant);
+    }
+
+    let num_families = families.len();
+    let mut variant_to_color = BTreeMap::new();
+    for (family_idx, members) in families.values().enumerate() {
+        // start at the green of the palette
+        let hue = (160.0 + family_idx as f32 * 360.0 / num_families as f32) % 360.0;
+        for (member_idx, variant) in members.iter().enumerate() {
+            let lightness = if members.len() == 1 {
+                0.55
+            } else {
+                0.35 + 0.4 * member_idx as f32 / (members.len() - 1) as f32
+            };
+            variant_to_color.insert(variant.to_string(), hsl_color(hue, 0.6, lightness));
+        }
+    }
+    variant_to_color
+}
+
+/// The `#RRGGBB` color of a hue in degrees, saturation and lightness in `0..=1`.
+fn hsl_color(hue: f32, saturation: f32, lightness: f32) -> String {
+    let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+    let h = hue / 60.0;
+    let x = chroma * (1.0 - (h % 2.0 - 1.0).abs());
+    let (r, g, b) = match h as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let m = lightness - chroma / 2.0;
+    let channel = |c: f32| ((c + m) * 255.0).round() as u8;
+    format!("#{:02X}{:02X}{:02X}", channel(r), channel(g), channel(b))
+}
+
+/// A hash which is stable across runs and platforms.
+fn fnv1a(text: &str) -> u64 {
+    text.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+const PALETTE: [&str; 5] = ["#3AB795", "#A0E8AF", "#86BAA1", "#EDEAD0", "#FFCF56"];
+
+/// The color of the `idx`th variant, starting from the end of the palette.
+fn palette_color(idx: usize) -> &'static str {
+    PALETTE[PALETTE.len() - 1 - idx % PALETTE.len()]
+}
+
+/// Regroups the benches by the bucket of their byte size, aggregating the values of a
+/// variant within a bucket with `stat`. Groups are labeled with the bucket range.
+pub fn bucket_benches(
+    name_to_benches: &BTreeMap<String, Vec<BenchData>>,
+    bucketing: Bucketing,
+    stat: Stat,
+    byte_unit: ByteUnit,
+) -> BTreeMap<String, Vec<BenchData>> {
+    // group name -> variant -> benches, keeping the variant order of the input
+    let mut buckets: BTreeMap<String, Vec<(String, Vec<&BenchData>)>> = BTreeMap::new();
+    for bench in name_to_benches.values().flatten() {
+        let (start, _) = bucketing.bucket(bench.num_bytes);
+        let group_name = format!("{}/{}", bench.bench_name, start);
+        let variants = buckets.entry(group_name).or_default();
+        match variants
+            .iter_mut()
+            .find(|(variant, _)| *variant == bench.variant)
+        {
+            Some((_, benches)) => benches.push(bench),
+            None => variants.push((bench.variant.to_string(), vec![bench])),
+        }
+    }
+
+    buckets
+        .into_iter()
+        .map(|(group_name, variants)| {
+            let benches = variants
+                .into_iter()
+                .map(|(variant, benches)| {
+                    let first = benches[0];
+                    let (start, end) = bucketing.bucket(first.num_bytes);
+                    let gbs: Vec<f64> = benches.iter().map(|bench| bench.gbs).collect();
+                    let medians: Option<Vec<f64>> =
+                        benches.iter().map(|bench| bench.median_gbs).collect();
+                    BenchData {
+                        bench_name: first.bench_name.to_string(),
+                        group_name: group_name.to_string(),
+                        variant,
+                        num_bytes: start as u32,
+                        gbs: stat.apply(&gbs),
+                        median_gbs: medians.map(|medians| stat.apply(&medians)),
+                        bar_label: first.bar_label.clone(),
+                        iterations: benches.iter().map(|bench| bench.iterations).sum(),
+                        samples: benches.iter().map(|bench| bench.samples).sum(),
+                        percentiles: None,
+                        cv: None,
+                        metrics: aggregate_metrics(&benches, stat),
+                        group_label: Some(format!(
+                            "{}–{}",
+                            humanize_bytes(start, byte_unit),
+                            humanize_bytes(end, byte_unit)
+                        )),
+                    }
+                })
+                .collect();
+            (group_name, benches)
+        })
+        .collect()
+}
+
+pub fn normalize_to_baseline(
+    name_to_benches: &mut BTreeMap<String, Vec<BenchData>>,
+    baseline: f64,
+) {
+    for bench in name_to_benches.values_mut().flatten() {
+        bench.gbs /= baseline;
+        if let Some(median) = bench.median_gbs.as_mut() {
+            *median /= baseline;
+        }
+        for percentile in bench.percentiles.iter_mut().flatten() {
+            *percentile /= baseline;
+        }
+    }
+}
+
+/// Removes all benches with a value below `min_value` and groups left without any bench.
+pub fn drop_below_min_value(
+    name_to_benches: &mut BTreeMap<String, Vec<BenchData>>,
+    min_value: f64,
+    warnings: &mut Warnings,
+) {
+    for (group_name, benches) in name_to_benches.iter_mut() {
+        benches.retain(|bench| {
+            let keep = bench.gbs >= min_value;
+            if !keep {
+                warnings.add(
+                    "below_min_value",
+                    format!(
+                        "dropped {} {} in {}, {:.2} is below the minimum value {}",
+                        bench.bench_name, bench.variant, group_name, bench.gbs, min_value
+                    ),
+                    None,
+                );
+            }
+            keep
+        });
+    }
+    name_to_benches.retain(|_, benches| !benches.is_empty());
+}
+
+/// Merges all groups whose highest value is below `threshold` into a single `other` group,
+/// with one bench per variant aggregated by `stat`. A single small group is kept as is.
+///
+/// Returns the name of the merged group, which is `other #2` etc. if a group named `other` is
+/// kept.
+pub fn merge_into_other(
+    name_to_benches: &mut BTreeMap<String, Vec<BenchData>>,
+    threshold: f64,
+    stat: Stat,
+) -> Option<String> {
+    let small: Vec<String> = name_to_benches
+        .iter()
+        .filter(|(_, benches)| benches.iter().all(|bench| bench.gbs < threshold))
+        .map(|(name, _)| name.to_string())
+        .collect();
+    if small.len() < 2 {
+        return None;
+    }
+    // variant -> benches, keeping the variant order of the input
+    let mut variants: Vec<(String, Vec<BenchData>)> = vec![];
+    for name in small {
+        log::debug!("merging {} into other", name);
+        for bench in name_to_benches.remove(&name).unwrap() {
+            match variants
+                .iter_mut()
+                .find(|(variant, _)| *variant == bench.variant)
+            {
+                Some((_, benches)) => benches.push(bench),
+                None => variants.push((bench.variant.to_string(), vec![bench])),
+            }
+        }
+    }
+    let mut other_name = "other".to_string();
+    for idx in 2.. {
+        if !name_to_benches.contains_key(&other_name) {
+            break;
+        }
+        other_name = format!("other #{}", idx);
+    }
+    let other = variants
+        .into_iter()
+        .map(|(variant, benches)| {
+            let gbs: Vec<f64> = benches.iter().map(|bench| bench.gbs).collect();
+            let medians: Option<Vec<f64>> = benches.iter().map(|bench| bench.median_gbs).collect();
+            BenchData {
+                bench_name: benches[0].bench_name.to_string(),
+                group_name: other_name.to_string(),
+                variant,
+                num_bytes: 0,
+                gbs: stat.apply(&gbs),
+                median_gbs: medians.map(|medians| stat.apply(&medians)),
+                bar_label: None,
+                group_label: Some("other".to_string()),
+                iterations: benches.iter().map(|bench| bench.iterations).sum(),
+                samples: benches.iter().map(|bench| bench.samples).sum(),
+                percentiles: None,
+                cv: None,
+                metrics: aggregate_metrics(&benches.iter().collect::<Vec<_>>(), stat),
+            }
+        })
+        .collect();
+    name_to_benches.insert(other_name.to_string(), other);
+    Some(other_name)
+}
+
+/// Aggregates the metrics all benches have.
+fn aggregate_metrics(benches: &[&BenchData], stat: Stat) -> BTreeMap<String, f64> {
+    benches[0]
+        .metrics
+        .keys()
+        .filter_map(|name| {
+            let values: Option<Vec<f64>> = benches
+                .iter()
+                .map(|bench| bench.metrics.get(name).copied())
+                .collect();
+            Some((name.to_string(), stat.apply(&values?)))
+        })
+        .collect()
+}
+
+/// Copies the benches with the value of `metric` instead of the throughput, `gbs` or a name in
+/// [`BenchData::metrics`]. Benches without the metric get 0, so every panel of a
+/// [`render_panels`] plot has the same groups.
+pub fn select_metric(
+    name_to_benches: &BTreeMap<String, Vec<BenchData>>,
+    metric: &str,
+    warnings: &mut Warnings,
+) -> BTreeMap<String, Vec<BenchData>> {
+    if metric == "gbs" {
+        return name_to_benches.clone();
+    }
+    let mut num_missing = 0;
+    let selected = name_to_benches
+        .iter()
+        .map(|(name, benches)| {
+            let benches = benches
+                .iter()
+                .map(|bench| {
+                    let value = bench.metrics.get(metric).copied().unwrap_or_else(|| {
+                        num_missing += 1;
+                        0.0
+                    });
+                    BenchData {
+                        gbs: value,
+                        median_gbs: None,
+                        percentiles: None,
+                        cv: None,
+                        ..bench.clone()
+                    }
+                })
+                .collect();
+            (name.to_string(), benches)
+        })
+        .collect();
+    if num_missing > 0 {
+        warnings.add(
+            "missing_field",
+            format!("{} benchmarks have no {}, drawing 0", num_missing, metric),
+            None,
+        );
+    }
+    selected
+}
+
+/// Scales all values to percent of the highest value, which is the highest 95th percentile for
+/// box plots. Returns the highest value, to scale other values like the overlay line the same
+/// way.
+pub fn normalize_to_percent_of_max(
+    name_to_benches: &mut BTreeMap<String, Vec<BenchData>>,
+    chart_type: ChartType,
+) -> Option<f64> {
+    let max_value = name_to_benches
+        .values()
+        .flatten()
+        .map(|bench| match (chart_type, bench.percentiles) {
+            (ChartType::Box, Some(percentiles)) => bench.gbs.max(percentiles[4]),
+            _ => bench.gbs,
+        })
+        .fold(0.0, f64::max);
+    if max_value <= 0.0 {
+        return None;
+    }
+    normalize_to_baseline(name_to_benches, max_value / 100.0);
+    Some(max_value)
+}
+
+/// Loads a file with a variant name per line. Empty lines and lines starting with `#` are
+/// ignored.
+pub fn load_variant_order(file_name: &str) -> Vec<String> {
+    load_list(file_name)
+}
+
+/// Loads a file with a benchmark id or pattern per line, for
+/// [`LoadOptions::id_allowlist`] and [`LoadOptions::id_denylist`]. Empty lines and lines
+/// starting with `#` are ignored.
+pub fn load_id_patterns(file_name: &str) -> Vec<String> {
+    load_list(file_name)
+}
+
+fn load_list(file_name: &str) -> Vec<String> {
+    std::fs::read_to_string(file_name)
+        .unwrap()
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| line.to_string())
+        .collect()
+}
+
+/// Matches `text` against `pattern`, where `*` matches any run of characters, e.g.
+/// `Compress/*/725` matches `Compress/lz4/725`.
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        // without a `*` the pattern has to match the whole text
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(pos) => rest = &rest[pos + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+/// Sorts the benches of every group by the position of their variant in `order`. Unlisted
+/// variants follow in alphabetical order.
+pub fn order_variants(name_to_benches: &mut BTreeMap<String, Vec<BenchData>>, order: &[String]) {
+    for benches in name_to_benches.values_mut() {
+        benches.sort_by_key(|bench| variant_rank(order, &bench.variant));
+    }
+}
+
+fn variant_rank(order: &[String], variant: &str) -> (usize, String) {
+    let position = order.iter().position(|el| el == variant);
+    (position.unwrap_or(order.len()), variant.to_string())
+}
+
+/// Returns the mean value of every variant across all groups.
+fn variant_means(name_to_benches: &BTreeMap<String, Vec<BenchData>>) -> BTreeMap<String, f64> {
+    let mut values: BTreeMap<String, Vec<f64>> = BTreeMap::new();
+    for bench in name_to_benches.values().flatten() {
+        values
+            .entry(bench.variant.to_string())
+            .or_default()
+            .push(bench.gbs);
+    }
+    values
+        .into_iter()
+        .map(|(variant, values)| (variant, Stat::Mean.apply(&values)))
+        .collect()
+}
+
+/// Returns the min and max value of every variant across all groups.
+fn variant_ranges(
+    name_to_benches: &BTreeMap<String, Vec<BenchData>>,
+) -> BTreeMap<String, (f64, f64)> {
+    let mut ranges: BTreeMap<String, (f64, f64)> = BTreeMap::new();
+    for bench in name_to_benches.values().flatten() {
+        let range = ranges
+            .entry(bench.variant.to_string())
+            .or_insert((bench.gbs, bench.gbs));
+        range.0 = range.0.min(bench.gbs);
+        range.1 = range.1.max(bench.gbs);
+    }
+    ranges
+}
+
+/// Builds the display label of every group by its name, disambiguating labels which are shared
+/// by multiple groups according to `mode`. Counters are assigned in the order of `groups`.
+/// `overrides` maps a group name or its number of bytes to a label.
+pub fn group_labels(
+    groups: &[(&String, &Vec<BenchData>)],
+    overrides: &BTreeMap<String, String>,
+    byte_unit: ByteUnit,
+    mode: DuplicateLabels,
+    warnings: &mut Warnings,
+) -> Vec<String> {
+    let mut labels: Vec<String> = groups
+        .iter()
+        .map(|(name, group)| {
+            overrides
+                .get(*name)
+                .or_else(|| overrides.get(&group[0].num_bytes.to_string()))
+                .or(group[0].group_label.as_ref())
+                .cloned()
+                .unwrap_or_else(|| num_bytes_to_name(group[0].num_bytes, byte_unit))
+        })
+        .collect();
+    let bench_names: Vec<&str> = groups
+        .iter()
+        .map(|(_, group)| group[0].bench_name.as_str())
+        .collect();
+
+    match mode {
+        DuplicateLabels::Bench => {
+            for idx in duplicate_label_indices(&labels) {
+                labels[idx] = format!("{} ({})", labels[idx], bench_names[idx]);
+            }
+            // Groups of the same bench may still collide
+            append_counter_to_duplicates(&mut labels);
+        }
+        DuplicateLabels::Counter => append_counter_to_duplicates(&mut labels),
+        DuplicateLabels::Warn => {
+            let duplicates: BTreeSet<&String> = duplicate_label_indices(&labels)
+                .into_iter()
+                .map(|idx| &labels[idx])
+                .collect();
+            for label in duplicates {
+                warnings.add(
+                    "duplicate_label",
+                    format!("multiple groups share the label {:?}", label),
+                    None,
+                );
+            }
+        }
+    }
+    labels
+}
+
+fn duplicate_label_indices(labels: &[String]) -> Vec<usize> {
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for label in labels {
+        *counts.entry(label).or_default() += 1;
+    }
+    (0..labels.len())
+        .filter(|idx| counts[labels[*idx].as_str()] > 1)
+        .collect()
+}
+
+fn append_counter_to_duplicates(labels: &mut [String]) {
+    let mut seen: BTreeMap<String, usize> = BTreeMap::new();
+    for idx in duplicate_label_indices(labels) {
+        let counter = seen.entry(labels[idx].to_string()).or_default();
+        *counter += 1;
+        labels[idx] = format!("{} #{}", labels[idx], counter);
+    }
+}
+
+/// The group label of a byte size. The sizes of the lz4 benchmark datasets are named after the
+/// dataset and don't use `byte_unit`.
+pub fn num_bytes_to_name(num_bytes: u32, byte_unit: ByteUnit) -> String {
+    match num_bytes {
+        725 => "725b Text".to_string(),
+        66675 => "66K JSON".to_string(),
+        64723 => "65K Text".to_string(),
+        9991663 => "10Mb Dickens".to_string(),
+        34308 => "34K Text".to_string(),
+        _ => humanize_bytes(num_bytes as u64, byte_unit),
+    }
+}
+
+/// Formats a byte size with three significant digits, e.g. `9.99 MB` or `9.53 MiB`.
+pub fn humanize_bytes(num_bytes: u64, byte_unit: ByteUnit) -> String {
+    let (divisor, suffixes) = match byte_unit {
+        ByteUnit::Si => (1000.0, ["B", "KB", "MB", "GB", "TB"]),
+        ByteUnit::Binary => (1024.0, ["B", "KiB", "MiB", "GiB", "TiB"]),
+    };
+    let mut value = num_bytes as f64;
+    let mut suffix_idx = 0;
+    while value >= divisor && suffix_idx < suffixes.len() - 1 {
+        value /= divisor;
+        suffix_idx += 1;
+    }
+    if suffix_idx == 0 {
+        return format!("{} {}", num_bytes, suffixes[0]);
+    }
+    let decimals = if value >= 100.0 {
+        0
+    } else if value >= 10.0 {
+        1
+    } else {
+        2
+    };
+    format!("{:.*} {}", decimals, value, suffixes[suffix_idx])
+}
+
+/// Shortens `text` to `max_len` characters by replacing its middle with an ellipsis, e.g.
+/// `lz4_f…rust` for `lz4_flex_rust` and 10.
+pub fn abbreviate(text: &str, max_len: usize) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    if chars.len() <= max_len {
+        return text.to_string();
+    }
+    let keep = max_len.saturating_sub(1);
+    let head: String = chars[..keep.div_ceil(2)].iter().collect();
+    let tail: String = chars[chars.len() - keep / 2..].iter().collect();
+    format!("{}…{}", head, tail)
+}
+
+const X_AXIS_SPACE: f32 = 80.0;
+const DELTA_CHART_HEIGHT: f32 = 160.0;
+const AXIS_COLOR: &str = "#000000";
+const GRID_COLOR: &str = "#999999";
+const TITLE_FONT_SIZE: f32 = 16.0;
+const OVERLAY_LINE_COLOR: &str = "#FFD166";
+const NOISE_BADGE_COLOR: &str = "#E4572E";
+const BACKGROUND_GRADIENT_ID: &str = "background-gradient";
+const REGRESSION_COLOR: &str = "#D62828";
+const OTHER_GROUP_COLOR: &str = "#8D8D8D";
+#[derive(Debug, Clone)]
+pub struct GroupBarOptions {
+    pub total_width: f32,
+    pub total_height: f32,
+    /// chart padding from border
+    pub chart_area_to_border_padding: f32,
+    /// padding between bars inside group
+    pub bar_padding: f32,
+    pub print_delta: bool,
+    pub delta_position: DeltaPosition,
+    /// draw the delta chart below the chart. The groups are compared against the bar of the
+    /// `baseline_variant` if set, otherwise against `baseline`
+    pub delta_chart: bool,
+    /// draw the top and right border of the plot area
+    pub plot_frame: bool,
+    pub title_background: Option<String>,
+    pub data_labels: DataLabels,
+    /// label bars with their text label instead of the value, if they have one
+    pub show_bar_labels: bool,
+    /// draw a line across each bar at its median
+    pub median_marker: bool,
+    /// rotation of the whole document in degrees, 0, 90 or 270
+    pub rotate: u32,
+    pub y_axis_label: String,
+    /// group label and text of vertical marker lines
+    pub markers: Vec<(String, String)>,
+    /// factor for all stroke widths, for rasterizing at a high resolution
+    pub stroke_scale: f32,
+    pub axis_number_format: AxisNumberFormat,
+    /// top of the y axis instead of the highest value of the chart, e.g. to share the
+    /// scale between charts
+    pub y_max: Option<f32>,
+    pub chart_type: ChartType,
+    /// draw only the label of every nth group
+    pub label_every_nth: usize,
+    pub show_legend: bool,
+    /// appended to the numbers of the y axis and the data labels, e.g. `%`
+    pub value_suffix: String,
+    /// draw a vertical line between neighbouring groups
+    pub group_separators: bool,
+    /// let the bars grow from the x axis when the document is loaded
+    pub animate: bool,
+    /// abbreviate longer group and legend labels. The legend is abbreviated when it is built,
+    /// so that a legend range isn't cut off
+    pub max_label_length: Option<usize>,
+    /// mark bars with a coefficient of variation above this as noisy
+    pub cv_threshold: Option<f32>,
+    /// print the change of the mean of each group versus the group before it
+    pub delta_vs_previous: bool,
+    /// top and bottom color of a vertical gradient used instead of the solid background
+    pub background_gradient: Option<(String, String)>,
+    /// round the x and width of bars to whole pixels, for crisp edges
+    pub snap_bars: bool,
+    /// outline bars more than this many percent worse than their baseline, see
+    /// [`bar_regression`]
+    pub regression_threshold: Option<f32>,
+    /// the values are a cost like a duration, so higher values are regressions. Otherwise
+    /// they are a throughput and lower values are regressions
+    pub lower_is_better: bool,
+    /// the variant of each group the other bars are compared against for
+    /// `regression_threshold`, otherwise values are expected to be normalized to the baseline
+    pub baseline_variant: Option<String>,
+    /// the value of the baseline without a `baseline_variant`, 1 for values normalized to it.
+    /// Values which are scaled further, e.g. to percent of the maximum, scale it the same way
+    pub baseline: f32,
+}
+impl Default for GroupBarOptions {
+    fn default() -> Self {
+        GroupBarOptions {
+            total_width: 800.0,
+            total_height: 600.0,
+            chart_area_to_border_padding: 10.0,
+            bar_padding: 3.0,
+            print_delta: false,
+            delta_position: DeltaPosition::AboveMax,
+            delta_chart: false,
+            plot_frame: false,
+            title_background: None,
+            data_labels: DataLabels::None,
+            show_bar_labels: false,
+            median_marker: false,
+            rotate: 0,
+            y_axis_label: "Gb/s".to_string(),
+            markers: vec![],
+            stroke_scale: 1.0,
+            axis_number_format: AxisNumberFormat::Auto,
+            y_max: None,
+            chart_type: ChartType::Bar,
+            label_every_nth: 1,
+            show_legend: true,
+            value_suffix: String::new(),
+            group_separators: false,
+            animate: false,
+            max_label_length: None,
+            cv_threshold: None,
+            delta_vs_previous: false,
+            background_gradient: None,
+            snap_bars: false,
+            regression_threshold: None,
+            lower_is_better: false,
+            baseline_variant: None,
+            baseline: 1.0,
+        }
+    }
+}
+impl GroupBarOptions {
+    fn get_available_graph_width(&self) -> f32 {
+        let y_axis_space = 80.0;
+        self.total_width - y_axis_space - self.chart_area_to_border_padding * 2.0
+    }
+    fn get_available_graph_height(&self) -> f32 {
+        let x_axis_space = X_AXIS_SPACE;
+        self.total_height - x_axis_space - self.chart_area_to_border_padding * 2.0
+    }
+    /// Shortens the label to `max_label_length`, if set.
+    fn abbreviate(&self, label: &str) -> String {
+        match self.max_label_length {
+            Some(max_len) => abbreviate(label, max_len),
+            None => label.to_string(),
+        }
+    }
+    /// total height including the delta chart
+    fn get_document_height(&self) -> f32 {
+        if self.delta_chart {
+            self.total_height + DELTA_CHART_HEIGHT
+        } else {
+            self.total_height
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Group {
+    pub label: String,
+    /// the bench of the benchmarks in this group, empty for groups added with
+    /// [`Chart::add_group`]
+    pub bench_name: String,
+    pub bars: Vec<Bar>,
+    /// value of the overlay line at this group
+    pub expected: Option<f32>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Bar {
+    pub variant: String,
+    pub value: f32,
+    pub color: String,
+    /// text shown instead of the value with `show_bar_labels`
+    pub label: Option<String>,
+    pub median: Option<f32>,
+    /// shown by viewers when hovering the bar
+    pub tooltip: Option<String>,
+    /// the 5th, 25th, 50th, 75th and 95th percentile for box plots
+    pub percentiles: Option<[f32; 5]>,
+    /// coefficient of variation of the measurement
+    pub cv: Option<f32>,
+}
+
+/// The change of the bar versus its baseline in percent, if it is worse than the
+/// `regression_threshold` of the options. The baseline is the bar of the `baseline_variant` in the
+/// group or `baseline` of the options. Lower values are worse, unless `lower_is_better` is set.
+pub fn bar_regression(options: &GroupBarOptions, group: &Group, bar: &Bar) -> Option<f32> {
+    let threshold = options.regression_threshold?;
+    let baseline = match &options.baseline_variant {
+        Some(variant) if bar.variant == *variant => return None,
+        Some(variant) => group.bars.iter().find(|bar| bar.variant == *variant)?.value,
+        None => options.baseline,
+    };
+    let change = (bar.value - baseline) / baseline * 100.0;
+    let worse = if options.lower_is_better {
+        change > threshold
+    } else {
+        change < -threshold
+    };
+    worse.then_some(change)
+}
+
+fn compute_y_for_value(options: &GroupBarOptions, val: f32, max_value: f32) -> f32 {
+    let max_height = options.get_available_graph_height();
+    let bar_start = max_height + options.chart_area_to_border_padding;
+    let height = max_height * (val / max_value);
+    bar_start - height
+}
+
+fn draw_group(
+    doc: SVGGroup,
+    options: &GroupBarOptions,
+    groups: &Group,
+    group_idx: usize,
+    group_start_x: f32,
+    bar_width: f32,
+    max_value: f32,
+) -> SVGGroup {
+    let bar_padding = options.bar_padding;
+    let max_height = options.get_available_graph_height();
+    let bar_start = max_height + options.chart_area_to_border_padding;
+    let mut group = doc;
+    let mut bar_x = group_start_x;
+    for run in groups.bars.iter() {
+        let Bar {
+            value: val,
+            color,
+            label,
+            median,
+            tooltip,
+            ..
+        } = run;
+        if options.chart_type == ChartType::Box && run.percentiles.is_some() {
+            group = draw_box(group, options, groups, run, bar_x, bar_width, max_value);
+            bar_x += bar_width + bar_padding;
+            continue;
+        }
+        let (x, width) = bar_edges(bar_x, bar_width, options.snap_bars);
+        let bar = BarGeometry {
+            x,
+            y: compute_y_for_value(options, *val, max_value),
+            width,
+            height: max_height * (val / max_value),
+        };
+        let mut rect = Rectangle::new()
+            .set("x", bar.x)
+            .set("y", bar.y)
+            .set("width", bar.width)
+            .set("height", bar.height)
+            .set("fill", color.to_string());
+        if bar_regression(options, groups, run).is_some() {
+            rect = rect
+                .set("stroke", REGRESSION_COLOR)
+                .set("stroke-width", 2.0 * options.stroke_scale);
+        }
+        if let Some(tooltip) = tooltip {
+            rect = rect.add(Title::new().add(svg::node::Text::new(tooltip.to_string())));
+        }
+        if options.animate {
+            // grow from the x axis, a bit slower for every group. A later begin would show
+            // the final bar until the animation starts
+            let dur = format!("{}ms", 600 + group_idx * 100);
+            for (attribute, from, to) in [("height", 0.0, bar.height), ("y", bar_start, bar.y)] {
+                let animate = element::Animate::new()
+                    .set("attributeName", attribute)
+                    .set("from", from)
+                    .set("to", to)
+                    .set("dur", dur.to_string())
+                    .set("fill", "freeze");
+                rect = rect.add(animate);
+            }
+        }
+
+        group = group.add(rect);
+        if let (Some(median), true) = (median, options.median_marker) {
+            let y = compute_y_for_value(options, *median, max_value);
+            let marker = Line::new()
+                .set("x1", bar.x)
+                .set("y1", y)
+                .set("x2", bar.x + bar.width)
+                .set("y2", y)
+                .set("stroke", contrasting_text_color(color))
+                .set("stroke-width", 2.0 * options.stroke_scale);
+            group = group.add(marker);
+        }
+        let text = match label {
+            Some(label) if options.show_bar_labels => Some(label.to_string()),
+            _ if options.data_labels != DataLabels::None => {
+                Some(format!("{:.2}{}", val, options.value_suffix))
+            }
+            _ => None,
+        };
+        if let Some(text) = text {
+            group = draw_data_label(group, options, text, color, &bar);
+        }
+        if let (Some(cv), Some(threshold)) = (run.cv, options.cv_threshold) {
+            if cv > threshold {
+                group = draw_noise_badge(group, &bar, cv);
+            }
+        }
+        bar_x += (bar_width) + bar_padding;
+    }
+
+    if group_idx.is_multiple_of(options.label_every_nth.max(1)) {
+        let mut node = svg::node::element::Text::new()
+            .set("text-anchor", "left")
+            .set("x", group_start_x)
+            .set("y", bar_start + 20.0);
+        node.append(svg::node::Text::new(options.abbreviate(&groups.label)));
+        group = group.add(node);
+    }
+
+    if options.print_delta {
+        let min = groups
+            .bars
+            .iter()
+            .map(|bar| bar.value)
+            .min_by(|a, b| a.partial_cmp(b).unwrap())
+            .unwrap();
+
+        let (max_idx, max) = groups
+            .bars
+            .iter()
+            .map(|bar| bar.value)
+            .enumerate()
+            .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+            .unwrap();
+
+        let group_width = groups.bars.len() as f32 * (bar_width + bar_padding) - bar_padding;
+        let (x, y) = match options.delta_position {
+            DeltaPosition::AboveMax => (
+                group_start_x + max_idx as f32 * (bar_width + bar_padding) + bar_width / 2.0,
+                compute_y_for_value(options, max, max_value) - 10.0,
+            ),
+            DeltaPosition::AboveGroup => (
+                group_start_x + group_width / 2.0,
+                compute_y_for_value(options, max, max_value) - 10.0,
+            ),
+            DeltaPosition::Top => (
+                group_start_x + group_width / 2.0,
+                options.chart_area_to_border_padding + 10.0,
+            ),
+        };
+        let mut node = svg::node::element::Text::new()
+            .set("text-anchor", "middle")
+            .set("x", x)
+            .set("y", y);
+        node.append(svg::node::Text::new(get_percent_difference(min, max)));
+        group = group.add(node);
+    }
+
+    group
+}
+
+/// Draws a dot above the bar, with the coefficient of variation as tooltip.
+fn draw_noise_badge(group: SVGGroup, bar: &BarGeometry, cv: f32) -> SVGGroup {
+    let badge = element::Circle::new()
+        .set("cx", bar.x + bar.width / 2.0)
+        .set("cy", bar.y - 6.0)
+        .set("r", 3)
+        .set("fill", NOISE_BADGE_COLOR)
+        .add(Title::new().add(svg::node::Text::new(format!(
+            "noisy, CV {:.1}%",
+            cv * 100.0
+        ))));
+    group.add(badge)
+}
+
+/// Draws a box from the 25th to the 75th percentile with a line at the median and whiskers
+/// to the 5th and 95th percentile.
+fn draw_box(
+    mut group: SVGGroup,
+    options: &GroupBarOptions,
+    bar_group: &Group,
+    run: &Bar,
+    x: f32,
+    width: f32,
+    max_value: f32,
+) -> SVGGroup {
+    let Some(percentiles) = run.percentiles else {
+        return group;
+    };
+    let [p5, p25, p50, p75, p95] = percentiles.map(|p| compute_y_for_value(options, p, max_value));
+    let center = x + width / 2.0;
+    let whisker_color = GRID_COLOR;
+    let line = |x1: f32, y1: f32, x2: f32, y2: f32, color: &str| {
+        Line::new()
+            .set("x1", x1)
+            .set("y1", y1)
+            .set("x2", x2)
+            .set("y2", y2)
+            .set("stroke", color.to_string())
+    };
+    group = group.add(line(center, p95, center, p75, whisker_color));
+    group = group.add(line(center, p25, center, p5, whisker_color));
+    for y in [p5, p95] {
+        group = group.add(line(x + width / 4.0, y, x + width * 0.75, y, whisker_color));
+    }
+    let mut rect = Rectangle::new()
+        .set("x", x)
+        .set("y", p75)
+        .set("width", width)
+        .set("height", p25 - p75)
+        .set("fill", run.color.to_string());
+    if bar_regression(options, bar_group, run).is_some() {
+        rect = rect
+            .set("stroke", REGRESSION_COLOR)
+            .set("stroke-width", 2.0 * options.stroke_scale);
+    }
+    if let Some(tooltip) = &run.tooltip {
+        rect = rect.add(Title::new().add(svg::node::Text::new(tooltip.to_string())));
+    }
+    group = group.add(rect);
+    let median = line(x, p50, x + width, p50, contrasting_text_color(&run.color))
+        .set("stroke-width", 2.0 * options.stroke_scale);
+    group.add(median)
+}
+
+/// Draws the percentage change of the mean of every group against the baseline below the
+/// chart, using the same x positions as the groups. Groups without a bar of the
+/// `baseline_variant` get no delta.
+fn draw_delta_chart(
+    mut doc: SVGGroup,
+    options: &GroupBarOptions,
+    groups: &[Group],
+    group_start_x: &[f32],
+    bar_width: f32,
+) -> SVGGroup {
+    let deltas: Vec<Option<f32>> = groups
+        .iter()
+        .map(|group| {
+            let (baseline, bars): (f32, Vec<&Bar>) = match &options.baseline_variant {
+                Some(variant) => (
+                    group.bars.iter().find(|bar| bar.variant == *variant)?.value,
+                    group
+                        .bars
+                        .iter()
+                        .filter(|bar| bar.variant != *variant)
+                        .collect(),
+                ),
+                None => (options.baseline, group.bars.iter().collect()),
+            };
+            if bars.is_empty() {
+                return None;
+            }
+            let sum: f32 = bars.iter().map(|bar| bar.value).sum();
+            let mean = sum / bars.len() as f32;
+            Some((mean - baseline) / baseline * 100.0)
+        })
+        .collect();
+    let max_abs_delta = deltas
+        .iter()
+        .flatten()
+        .map(|delta| delta.abs())
+        .fold(0.0, f32::max)
+        .max(f32::EPSILON);
+
+    let label_space = 20.0;
+    let half_height = (DELTA_CHART_HEIGHT - label_space * 2.0) / 2.0;
+    let zero_y = options.total_height + label_space + half_height;
+    let axis_x_pos = X_AXIS_SPACE + options.chart_area_to_border_padding - 5.0;
+
+    let zero_line = Line::new()
+        .set("x1", axis_x_pos)
+        .set("y1", zero_y)
+        .set("x2", axis_x_pos + 5.0 + options.get_available_graph_width())
+        .set("y2", zero_y)
+        .set("stroke", AXIS_COLOR);
+    doc = doc.add(zero_line);
+
+    let mut node = svg::node::element::Text::new()
+        .set("text-anchor", "middle")
+        .set("x", 30)
+        .set("y", zero_y + 4.0);
+    node.append(svg::node::Text::new("Δ %"));
+    doc = doc.add(node);
+
+    for ((group, delta), x) in groups.iter().zip(deltas).zip(group_start_x) {
+        let Some(delta) = delta else {
+            continue;
+        };
+        let num_bars = group.bars.len() as f32;
+        let width = num_bars * (bar_width + options.bar_padding) - options.bar_padding;
+        let height = half_height * (delta.abs() / max_abs_delta);
+        let (y, color, label_y) = if delta >= 0.0 {
+            (zero_y - height, "#3AB795", zero_y - height - 5.0)
+        } else {
+            (zero_y, "#E4572E", zero_y + height + 15.0)
+        };
+        let rect = Rectangle::new()
+            .set("x", *x)
+            .set("y", y)
+            .set("width", width)
+            .set("height", height)
+            .set("fill", color);
+        doc = doc.add(rect);
+
+        let mut node = svg::node::element::Text::new()
+            .set("text-anchor", "middle")
+            .set("font-size", 12)
+            .set("x", x + width / 2.0)
+            .set("y", label_y);
+        node.append(svg::node::Text::new(format!("{:+.2}%", delta)));
+        doc = doc.add(node);
+    }
+
+    doc
+}
+
+const DATA_LABEL_FONT_SIZE: f32 = 10.0;
+
+/// The x and width of a bar at `x`, with both edges rounded to whole pixels if `snap` is set.
+/// Rounding the edges instead of the width keeps the rounding errors from adding up.
+fn bar_edges(x: f32, width: f32, snap: bool) -> (f32, f32) {
+    if snap {
+        (x.round(), (x + width).round() - x.round())
+    } else {
+        (x, width)
+    }
+}
+
+/// The position and size of a drawn bar.
+#[derive(Debug, Clone, Copy)]
+struct BarGeometry {
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+}
+
+/// Draws the label of a bar, either above it or centered inside and rotated when it
+/// doesn't fit the bar width. Bars too short for an inside label fall back to above.
+fn draw_data_label(
+    group: SVGGroup,
+    options: &GroupBarOptions,
+    text: String,
+    color: &str,
+    bar: &BarGeometry,
+) -> SVGGroup {
+    let text_width = text.chars().count() as f32 * DATA_LABEL_FONT_SIZE * 0.6;
+    let rotate = text_width > bar.width;
+    let text_extent = if rotate {
+        text_width
+    } else {
+        DATA_LABEL_FONT_SIZE
+    };
+    let center_x = bar.x + bar.width / 2.0;
+
+    let mut node = svg::node::element::Text::new()
+        .set("font-size", DATA_LABEL_FONT_SIZE)
+        .set("text-anchor", "middle");
+    if options.data_labels == DataLabels::Inside && bar.height >= text_extent + 4.0 {
+        let center_y = bar.y + bar.height / 2.0;
+        node = node
+            .set("x", center_x)
+            .set("y", center_y)
+            .set("dominant-baseline", "central")
+            .set("fill", contrasting_text_color(color));
+        if rotate {
+            node = node.set(
+                "transform",
+                format!("rotate(-90,{},{})", center_x, center_y),
+            );
+        }
+    } else {
+        node = node.set("x", center_x).set("y", bar.y - 4.0);
+    }
+    node.append(svg::node::Text::new(text));
+    group.add(node)
+}
+
+/// Returns black or white, whichever is more readable on the `#RRGGBB` background.
+pub fn contrasting_text_color(background: &str) -> &'static str {
+    let hex = background.trim_start_matches('#');
+    let channel = |idx: usize| {
+        let value = hex
+            .get(idx..idx + 2)
+            .and_then(|c| u8::from_str_radix(c, 16).ok())
+            .unwrap_or(0) as f32
+            / 255.0;
+        // sRGB to linear
+        if value <= 0.03928 {
+            value / 12.92
+        } else {
+            ((value + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    let luminance = 0.2126 * channel(0) + 0.7152 * channel(2) + 0.0722 * channel(4);
+    if luminance > 0.179 {
+        "#000000"
+    } else {
+        "#FFFFFF"
+    }
+}
+
+fn get_percent_difference(min: f32, max: f32) -> String {
+    let difference = max - min;
+    let percent_difference = (difference / min) * 100.0;
+    format!("+{:.2}%", percent_difference)
+}
+
+/// The change of the mean bar value of each group versus the previous group in percent, `None`
+/// for the first group of each bench.
+pub fn deltas_vs_previous(groups: &[Group]) -> Vec<Option<f32>> {
+    let means: Vec<f32> = groups
+        .iter()
+        .map(|group| {
+            group.bars.iter().map(|bar| bar.value).sum::<f32>() / group.bars.len().max(1) as f32
+        })
+        .collect();
+    (0..means.len())
+        .map(|idx| {
+            let prev_idx = idx.checked_sub(1)?;
+            // the size steps of different benches don't compare
+            if groups[prev_idx].bench_name != groups[idx].bench_name {
+                return None;
+            }
+            let prev = means[prev_idx];
+            Some((means[idx] - prev) / prev * 100.0)
+        })
+        .collect()
+}
+
+/// The geometry of a rendered chart, for drawing custom decorations with
+/// [`render_grouped_bar_chart_with_hook`].
+#[derive(Debug, Clone)]
+pub struct ChartLayout {
+    /// left edge of the plot area, where the y axis is drawn
+    pub plot_x: f32,
+    /// top edge of the plot area
+    pub plot_y: f32,
+    pub plot_width: f32,
+    pub plot_height: f32,
+    /// the value at the top of the y axis
+    pub max_value: f32,
+    pub bar_width: f32,
+    pub bar_padding: f32,
+    /// the space reserved for each group
+    pub group_width: f32,
+    /// x of the first bar of each group
+    pub group_start_x: Vec<f32>,
+}
+
+impl ChartLayout {
+    /// Computes the layout of `groups` drawn with `options`.
+    pub fn new(options: &GroupBarOptions, groups: &[Group]) -> ChartLayout {
+        let max_value: f32 = options
+            .y_max
+            .unwrap_or_else(|| max_chart_value(groups, options.chart_type));
+        // an empty scale instead of dividing by zero, e.g. for a metric only missing values
+        let max_value = if max_value > 0.0 { max_value } else { 1.0 };
+
+        let available_graph_space = options.get_available_graph_width();
+        let group_width = available_graph_space / groups.len() as f32;
+
+        let max_num_bars_in_group = groups.iter().map(|g| g.bars.len()).max().unwrap();
+        let bar_width = if max_num_bars_in_group == 1 {
+            // a plain bar chart, leave a gap between the bars instead of grouping them
+            group_width * 0.7
+        } else {
+            (group_width / max_num_bars_in_group as f32).min(20.0)
+        };
+
+        let plot_x = X_AXIS_SPACE + options.chart_area_to_border_padding;
+        ChartLayout {
+            plot_x,
+            plot_y: options.chart_area_to_border_padding,
+            plot_width: available_graph_space,
+            plot_height: options.get_available_graph_height(),
+            max_value,
+            bar_width,
+            bar_padding: options.bar_padding,
+            group_width,
+            group_start_x: (0..groups.len())
+                .map(|idx| plot_x + idx as f32 * group_width)
+                .collect(),
+        }
+    }
+
+    /// The x of the left edge of the bar `bar_idx` in the group `group_idx`.
+    pub fn bar_x(&self, group_idx: usize, bar_idx: usize) -> f32 {
+        self.group_start_x[group_idx] + bar_idx as f32 * (self.bar_width + self.bar_padding)
+    }
+
+    /// The y coordinate of `value` on the y axis.
+    pub fn y_for_value(&self, value: f32) -> f32 {
+        self.plot_y + self.plot_height - self.plot_height * (value / self.max_value)
+    }
+}
+
+pub fn render_grouped_bar_chart(
+    title: &str,
+    doc: SVGGroup,
+    options: GroupBarOptions,
+    groups: &[Group],
+    legend: &[(String, String)],
+) -> SVGGroup {
+    render_grouped_bar_chart_with_hook(title, doc, options, groups, legend, |_, _| {})
+}
+
+/// Like [`render_grouped_bar_chart`], but calls `hook` with the layout of the chart after the
+/// standard rendering, to append custom elements like watermarks or annotations.
+pub fn render_grouped_bar_chart_with_hook(
+    title: &str,
+    mut doc: SVGGroup,
+    options: GroupBarOptions,
+    groups: &[Group],
+    legend: &[(String, String)],
+    mut hook: impl FnMut(&mut SVGGroup, &ChartLayout),
+) -> SVGGroup {
+    let layout = ChartLayout::new(&options, groups);
+    let (max_value, group_width, bar_width) =
+        (layout.max_value, layout.group_width, layout.bar_width);
+    let group_start_x = layout.group_start_x.clone();
+    let curr_group_x = layout.plot_x;
+
+    // inherited by all lines without an explicit width
+    if options.stroke_scale != 1.0 {
+        doc = doc.set("stroke-width", options.stroke_scale);
+    }
+
+    doc = draw_y_scale(
+        doc,
+        &options,
+        &options.y_axis_label,
+        curr_group_x,
+        max_value,
+    );
+    doc = draw_x_scale(doc, &options, curr_group_x);
+    if options.plot_frame {
+        doc = draw_plot_frame(doc, &options, curr_group_x);
+    }
+
+    for (idx, group) in groups.iter().enumerate() {
+        doc = draw_group(
+            doc,
+            &options,
+            group,
+            idx,
+            group_start_x[idx],
+            bar_width,
+            max_value,
+        );
+    }
+
+    if options.delta_chart {
+        doc = draw_delta_chart(doc, &options, groups, &group_start_x, bar_width);
+    }
+
+    let overlay_points: Vec<String> = groups
+        .iter()
+        .zip(&group_start_x)
+        .filter_map(|(group, start_x)| {
+            let bars_width =
+                group.bars.len() as f32 * (bar_width + options.bar_padding) - options.bar_padding;
+            let y = compute_y_for_value(&options, group.expected?, max_value);
+            Some(format!("{},{}", start_x + bars_width / 2.0, y))
+        })
+        .collect();
+    if !overlay_points.is_empty() {
+        let line = element::Polyline::new()
+            .set("points", overlay_points.join(" "))
+            .set("fill", "none")
+            .set("stroke", OVERLAY_LINE_COLOR)
+            .set("stroke-width", 2.0 * options.stroke_scale);
+        doc = doc.add(line);
+    }
+
+    let bars_width = |group: &Group| {
+        group.bars.len() as f32 * (bar_width + options.bar_padding) - options.bar_padding
+    };
+    // the middle of the gap before the group
+    let gap_x = |idx: usize| match idx {
+        0 => group_start_x[0] - (group_width - bars_width(&groups[0])) / 2.0,
+        _ => (group_start_x[idx - 1] + bars_width(&groups[idx - 1]) + group_start_x[idx]) / 2.0,
+    };
+    if options.delta_vs_previous {
+        let deltas = deltas_vs_previous(groups);
+        for ((group, delta), start_x) in groups.iter().zip(deltas).zip(&group_start_x) {
+            let Some(delta) = delta.filter(|delta| delta.is_finite()) else {
+                continue;
+            };
+            let group_max = group.bars.iter().map(|bar| bar.value).fold(0.0, f32::max);
+            // stay clear of the min-max delta
+            let offset = if options.print_delta { 24.0 } else { 10.0 };
+            let y = (compute_y_for_value(&options, group_max, max_value) - offset)
+                .max(options.chart_area_to_border_padding + 12.0);
+            let mut node = svg::node::element::Text::new()
+                .set("text-anchor", "middle")
+                .set("font-size", 12)
+                .set("x", start_x + bars_width(group) / 2.0)
+                .set("y", y);
+            node.append(svg::node::Text::new(format!("{:+.2}%", delta)));
+            doc = doc.add(node);
+        }
+    }
+    if options.group_separators {
+        for idx in 1..groups.len() {
+            let x = gap_x(idx);
+            let separator = Line::new()
+                .set("x1", x)
+                .set("y1", options.chart_area_to_border_padding)
+                .set("x2", x)
+                .set(
+                    "y2",
+                    options.chart_area_to_border_padding + options.get_available_graph_height(),
+                )
+                .set("stroke", GRID_COLOR)
+                .set("stroke-opacity", 0.5);
+            doc = doc.add(separator);
+        }
+    }
+    for (label, text) in &options.markers {
+        let Some(idx) = groups.iter().position(|group| group.label == *label) else {
+            continue;
+        };
+        doc = draw_marker(doc, &options, gap_x(idx), text);
+    }
+
+    // Add legend group
+    if options.show_legend && !legend.is_empty() {
+        let mut legend_group = element::Group::new();
+        legend_group = draw_legend(legend_group, legend);
+        // Keep wide legends inside the chart
+        let legend_x = (options.get_available_graph_width() - 100.0).min(
+            options.total_width
+                - options.chart_area_to_border_padding
+                - get_legend_width(legend) as f32,
+        );
+        legend_group = legend_group.set(
+            "transform",
+            format!("translate({},{})", legend_x.max(0.0) as u32, 20),
+        );
+        doc = doc.add(legend_group);
+    }
+    //doc = doc.set("transform", "translate(0,50)");
+
+    // Add Title
+    let title_x = options.chart_area_to_border_padding + options.get_available_graph_width() - 70.0;
+    let title_y = TITLE_FONT_SIZE;
+    if let (Some(color), false) = (&options.title_background, title.is_empty()) {
+        // Estimate the text extent, bold glyphs are roughly 0.6em wide
+        let width = title.chars().count() as f32 * TITLE_FONT_SIZE * 0.6 + 12.0;
+        let height = TITLE_FONT_SIZE * 1.3;
+        let rect = Rectangle::new()
+            .set("x", title_x - width / 2.0)
+            .set("y", title_y - TITLE_FONT_SIZE)
+            .set("width", width)
+            .set("height", height)
+            .set("rx", 4)
+            .set("fill", color.to_string());
+        doc = doc.add(rect);
+    }
+    let mut node = svg::node::element::Text::new()
+        .set("text-anchor", "middle")
+        .set("font-weight", "bold")
+        .set("font-size", TITLE_FONT_SIZE)
+        .set("x", title_x)
+        .set("y", title_y);
+    node.append(svg::node::Text::new(title.to_string()));
+    doc = doc.add(node);
+
+    hook(&mut doc, &layout);
+
+    doc
+}
+
+fn get_legend_width(legend: &[(String, String)]) -> usize {
+    let longest_label = legend
+        .iter()
+        .map(|(label, _)| label.chars().count())
+        .max()
+        .unwrap();
+    // leave room for the color swatch on short labels
+    (longest_label * 9).max(70)
+}
+
+fn draw_legend(mut group: SVGGroup, legend: &[(String, String)]) -> SVGGroup {
+    group = group.set("fill", "#000000");
+
+    let legend_padding = 10;
+    let lebend_entry_height = 20;
+
+    let legend_width = get_legend_width(legend);
+    let legend_height = legend_padding * 2 + legend.len() * lebend_entry_height;
+    let rect = Rectangle::new()
+        .set("width", legend_width)
+        .set("height", legend_height)
+        .set("fill", "#FFFFFF")
+        .set("stroke", "#121212");
+    group = group.add(rect);
+    // entries without a color are headers, e.g. of a variant family, the others get indented
+    let has_headers = legend.iter().any(|(_, color)| color.is_empty());
+    let mut variant_y = legend_padding + 5;
+    for (label, color) in legend {
+        let mut node = svg::node::element::Text::new()
+            .set("font-size", 12)
+            .set(
+                "x",
+                if has_headers && !color.is_empty() {
+                    20
+                } else {
+                    10
+                },
+            )
+            .set("y", variant_y + 10);
+        node.append(svg::node::Text::new(label.to_string()));
+        if color.is_empty() {
+            group = group.add(node.set("font-weight", "bold"));
+            variant_y += lebend_entry_height;
+            continue;
+        }
+        group = group.add(node);
+
+        let rect = Rectangle::new()
+            .set("y", variant_y)
+            .set("x", legend_width - 30)
+            .set("width", 20)
+            .set("height", lebend_entry_height - 10)
+            .set("fill", color.to_string());
+        group = group.add(rect);
+        variant_y += lebend_entry_height;
+    }
+
+    group
+}
+
+fn draw_marker(mut group: SVGGroup, options: &GroupBarOptions, x: f32, text: &str) -> SVGGroup {
+    let top = options.chart_area_to_border_padding;
+    let line = Line::new()
+        .set("x1", x)
+        .set("y1", top)
+        .set("x2", x)
+        .set("y2", top + options.get_available_graph_height())
+        .set("stroke", GRID_COLOR)
+        .set("stroke-width", options.stroke_scale)
+        .set("stroke-dasharray", "4 3");
+    group = group.add(line);
+    let mut node = svg::node::element::Text::new()
+        .set("font-size", DATA_LABEL_FONT_SIZE)
+        .set("x", x + 3.0)
+        .set("y", top + DATA_LABEL_FONT_SIZE);
+    node.append(svg::node::Text::new(text.to_string()));
+    group.add(node)
+}
+
+fn draw_y_scale(
+    mut group: SVGGroup,
+    options: &GroupBarOptions,
+    axis_label: &str,
+    group_start_x: f32,
+    max_value: f32,
+) -> SVGGroup {
+    let num_markings = 8;
+
+    let axis_x_pos = group_start_x - 5.0;
+
+    let axis = Line::new()
+        .set("x1", axis_x_pos)
+        .set("y1", options.chart_area_to_border_padding)
+        .set("x2", axis_x_pos)
+        .set(
+            "y2",
+            options.chart_area_to_border_padding + options.get_available_graph_height(),
+        )
+        //.set("width", bar_width)
+        .set("stroke", AXIS_COLOR);
+
+    // Add ticks
+    let ticks = bar_axis_ticks(max_value, num_markings);
+    for val in ticks {
+        let y = compute_y_for_value(options, val, max_value);
+        let tick_line = Line::new()
+            .set("x1", axis_x_pos)
+            .set("y1", y)
+            .set("x2", axis_x_pos - 5.0)
+            .set("y2", y)
+            .set("stroke", AXIS_COLOR);
+        group = group.add(tick_line);
+
+        // Add grid
+        let tick_line = Line::new()
+            .set("x1", axis_x_pos - 5.0)
+            .set("y1", y)
+            .set(
+                "x2",
+                options.bar_padding + options.get_available_graph_width(),
+            )
+            .set("y2", y)
+            .set("stroke", GRID_COLOR);
+        group = group.add(tick_line);
+
+        let mut node = svg::node::element::Text::new()
+            .set("font-size", 12)
+            .set("direction", "rtl")
+            //.set("text-anchor", "right")
+            .set("x", axis_x_pos - 10.0)
+            .set("y", y + 4.0);
+        node.append(svg::node::Text::new(format!(
+            "{}{}",
+            options.axis_number_format.format(val),
+            options.value_suffix
+        )));
+        group = group.add(node);
+    }
+
+    let mid_point =
+        (options.chart_area_to_border_padding + options.get_available_graph_height()) / 2.0;
+    let mut node = svg::node::element::Text::new()
+        .set("text-anchor", "middle")
+        .set("x", 30)
+        .set("y", mid_point);
+    node.append(svg::node::Text::new(axis_label.to_string()));
+    group = group.add(node);
+
+    group = group.add(axis);
+
+    group
+}
+
+fn bar_axis_ticks(max: f32, num_ticks: usize) -> Vec<f32> {
+    let step_size = calc_step_size(max as f64, num_ticks as f64) as f32;
+    let mut ticks = Vec::with_capacity(num_ticks);
+    for i in 0..num_ticks {
+        let tick = i as f32 * step_size;
+        // ticks above the top of the axis would be drawn outside of the plot
+        if tick > max {
+            break;
+        }
+        ticks.push(tick);
+    }
+
+    ticks
+}
+
+fn calc_step_size(max_val: f64, target_steps: f64) -> f64 {
+    // calculate an initial guess at step size
+    let temp_step = max_val / target_steps;
+
+    // get the magnitude of the step size
+    let mag = f64::floor(f64::ln(temp_step) / std::f64::consts::LN_10);
+    let mag_pow = f64::powi(10.0, mag as i32);
+
+    // calculate most significant digit of the new step size
+    let mag_msd = f64::round(temp_step / mag_pow + 0.5);
+
+    // promote the MSD to either 1, 2, or 5
+    let mag_msd = if mag_msd > 5.0 {
+        10.0
+    } else if mag_msd > 2.0 {
+        5.0
+    } else if mag_msd > 1.0 {
+        2.0
+    } else {
+        1.0
+    };
+
+    mag_msd * mag_pow
+}
+
+/// Draws the top and right border, which close the frame formed by the axis lines.
+fn draw_plot_frame(mut group: SVGGroup, options: &GroupBarOptions, group_start_x: f32) -> SVGGroup {
+    let left = group_start_x - 5.0;
+    let right = group_start_x + options.get_available_graph_width();
+    let top = options.chart_area_to_border_padding;
+    let bottom = options.chart_area_to_border_padding + options.get_available_graph_height();
+
+    let top_line = Line::new()
+        .set("x1", left)
+        .set("y1", top)
+        .set("x2", right)
+        .set("y2", top)
+        .set("stroke", AXIS_COLOR);
+    group = group.add(top_line);
+
+    let right_line = Line::new()
+        .set("x1", right)
+        .set("y1", top)
+        .set("x2", right)
+        .set("y2", bottom)
+        .set("stroke", AXIS_COLOR);
+    group = group.add(right_line);
+
+    group
+}
+
+fn draw_x_scale(mut group: SVGGroup, options: &GroupBarOptions, group_start_x: f32) -> SVGGroup {
+    let rect = Line::new()
+        .set("x1", group_start_x - 5.0)
+        .set(
+            "y1",
+            options.chart_area_to_border_padding + options.get_available_graph_height(),
+        )
+        .set("x2", group_start_x + options.get_available_graph_width())
+        .set(
+            "y2",
+            options.chart_area_to_border_padding + options.get_available_graph_height(),
+        )
+        //.set("width", bar_width)
+        .set("stroke", AXIS_COLOR);
+
+    group = group.add(rect);
+
+    group
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bench(bench_name: &str, variant: &str, num_bytes: u32, gbs: f64) -> BenchData {
+        BenchData {
+            bench_name: bench_name.to_string(),
+            group_name: format!("{}/{}", bench_name, num_bytes),
+            variant: variant.to_string(),
+            num_bytes,
+            gbs,
+            median_gbs: None,
+            bar_label: None,
+            group_label: None,
+            iterations: None,
+            samples: None,
+            percentiles: None,
+            cv: None,
+            metrics: BTreeMap::new(),
+        }
+    }
+
+    /// Groups the benches by their group name, like `load_data`.
+    fn group(benches: Vec<BenchData>) -> BTreeMap<String, Vec<BenchData>> {
+        let mut name_to_benches: BTreeMap<String, Vec<BenchData>> = BTreeMap::new();
+        for bench in benches {
+            name_to_benches
+                .entry(bench.group_name.to_string())
+                .or_default()
+                .push(bench);
+        }
+        name_to_benches
+    }
+
+    fn chart(
+        name_to_benches: &BTreeMap<String, Vec<BenchData>>,
+        label_options: &LabelOptions,
+        options: GroupBarOptions,
+    ) -> Chart {
+        let variant_to_color = build_variant_to_color(name_to_benches, ColorMode::Ordered);
+        build_chart(
+            "test",
+            name_to_benches,
+            &variant_to_color,
+            label_options,
+            options,
+            &mut Warnings::default(),
+        )
+    }
+
+    /// The tags of the SVG containing `needle`.
+    fn tags_with<'a>(svg: &'a str, needle: &str) -> Vec<&'a str> {
+        svg.split('<').filter(|tag| tag.contains(needle)).collect()
+    }
+
+    /// The numeric value of the attribute `name` of a tag.
+    fn attribute(tag: &str, name: &str) -> f32 {
+        let start = tag.find(&format!(" {}=\"", name)).unwrap() + name.len() + 3;
+        tag[start..start + tag[start..].find('"').unwrap()]
+            .parse()
+            .unwrap()
+    }
+
+    #[test]
+    fn duplicate_labels_get_the_bench_name() {
+        let name_to_benches = group(vec![
+            bench("Compress", "lz4", 1000, 1.0),
+            bench("Decompress", "lz4", 1000, 2.0),
+        ]);
+        let labels = group_labels(
+            &name_to_benches.iter().collect::<Vec<_>>(),
+            &BTreeMap::new(),
+            ByteUnit::Si,
+            DuplicateLabels::Bench,
+            &mut Warnings::default(),
+        );
+        assert_eq!(labels, ["1.00 KB (Compress)", "1.00 KB (Decompress)"]);
+
+        let labels = group_labels(
+            &name_to_benches.iter().collect::<Vec<_>>(),
+            &BTreeMap::new(),
+            ByteUnit::Si,
+            DuplicateLabels::Counter,
+            &mut Warnings::default(),
+        );
+        assert_eq!(labels, ["1.00 KB #1", "1.00 KB #2"]);
+    }
+
+    #[test]
+    fn legend_range_shows_min_and_max() {
+        let name_to_benches = group(vec![
+            bench("Compress", "lz4", 1000, 1.5),
+            bench("Compress", "lz4", 2000, 3.25),
+            bench("Compress", "zstd", 1000, 0.5),
+        ]);
+        let label_options = LabelOptions {
+            legend_range: true,
+            ..Default::default()
+        };
+        let chart = chart(&name_to_benches, &label_options, GroupBarOptions::default());
+        let labels: Vec<&str> = chart
+            .legend
+            .iter()
+            .map(|(label, _)| label.as_str())
+            .collect();
+        assert_eq!(labels, ["lz4 [1.50–3.25]", "zstd [0.50–0.50]"]);
+    }
+
+    #[test]
+    fn normalize_to_baseline_divides_all_values() {
+        let mut lz4 = bench("Compress", "lz4", 1000, 3.0);
+        lz4.median_gbs = Some(6.0);
+        lz4.percentiles = Some([1.5, 3.0, 4.5, 6.0, 7.5]);
+        let mut name_to_benches = group(vec![lz4]);
+        normalize_to_baseline(&mut name_to_benches, 1.5);
+        let lz4 = &name_to_benches["Compress/1000"][0];
+        assert_eq!(lz4.gbs, 2.0);
+        assert_eq!(lz4.median_gbs, Some(4.0));
+        assert_eq!(lz4.percentiles, Some([1.0, 2.0, 3.0, 4.0, 5.0]));
+    }
+
+    #[test]
+    fn delta_chart_has_a_bar_per_group() {
+        let options = GroupBarOptions {
+            delta_chart: true,
+            ..Default::default()
+        };
+        let mut chart = Chart::new("test", options);
+        chart.add_group("a", &[("lz4", 1.2), ("zstd", 1.0)]);
+        chart.add_group("b", &[("lz4", 0.8), ("zstd", 0.9)]);
+        chart.add_group("c", &[("lz4", 1.5), ("zstd", 1.1)]);
+        let svg = chart.render().to_string();
+        let up = svg.matches("fill=\"#3AB795\"").count();
+        let down = svg.matches("fill=\"#E4572E\"").count();
+        assert_eq!((up, down), (2, 1));
+        assert!(svg.contains("+10.00%"));
+        assert!(svg.contains("-15.00%"));
+    }
+
+    #[test]
+    fn plot_frame_closes_the_plot_area() {
+        let options = GroupBarOptions {
+            plot_frame: true,
+            ..Default::default()
+        };
+        let mut chart = Chart::new("test", options);
+        chart.add_group("a", &[("lz4", 1.0)]);
+        let svg = chart.render().to_string();
+        // left and bottom are the axes, top and right the frame
+        for line in [
+            r#"x1="85" x2="85" y1="10" y2="510""#,
+            r#"x1="85" x2="790" y1="510" y2="510""#,
+            r#"x1="85" x2="790" y1="10" y2="10""#,
+            r#"x1="790" x2="790" y1="10" y2="510""#,
+        ] {
+            assert!(
+                svg.contains(&format!(r##"<line stroke="#000000" {}/>"##, line)),
+                "{}",
+                line
+            );
+        }
+
+        chart.options.plot_frame = false;
+        let svg = chart.render().to_string();
+        assert!(!svg.contains(r#"x1="790" x2="790" y1="10" y2="510""#));
+    }
+
+    #[test]
+    fn filtered_variants_are_not_in_the_legend() {
+        let mut name_to_benches = group(vec![
+            bench("Compress", "lz4", 1000, 2.0),
+            bench("Compress", "zstd", 1000, 0.1),
+        ]);
+        let variant_to_color = build_variant_to_color(&name_to_benches, ColorMode::Ordered);
+        drop_below_min_value(&mut name_to_benches, 1.0, &mut Warnings::default());
+        let chart = build_chart(
+            "test",
+            &name_to_benches,
+            &variant_to_color,
+            &LabelOptions::default(),
+            GroupBarOptions::default(),
+            &mut Warnings::default(),
+        );
+        let labels: Vec<&str> = chart
+            .legend
+            .iter()
+            .map(|(label, _)| label.as_str())
+            .collect();
+        assert_eq!(labels, ["lz4"]);
+    }
+
+    #[test]
+    fn title_background_fits_the_title() {
+        let options = GroupBarOptions {
+            title_background: Some("#222222".to_string()),
+            ..Default::default()
+        };
+        let mut chart = Chart::new("Throughput", options);
+        chart.add_group("a", &[("lz4", 1.0)]);
+        let svg = chart.render().to_string();
+        let rect = tags_with(&svg, "fill=\"#222222\"")[0];
+        let title = tags_with(&svg, "font-weight=\"bold\"")[0];
+        let (title_x, title_y) = (attribute(title, "x"), attribute(title, "y"));
+        // centered on the title, about 0.6em per glyph plus padding
+        let (x, width) = (attribute(rect, "x"), attribute(rect, "width"));
+        assert_eq!(x + width / 2.0, title_x);
+        let text_width = "Throughput".len() as f32 * TITLE_FONT_SIZE * 0.6;
+        assert!(width > text_width && width < text_width + 20.0);
+        let (y, height) = (attribute(rect, "y"), attribute(rect, "height"));
+        assert!(y <= title_y - TITLE_FONT_SIZE * 0.8 && y + height >= title_y);
+    }
+
+    #[test]
+    fn title_baseline_is_inside_the_document() {
+        let mut chart = Chart::new("Throughput", GroupBarOptions::default());
+        chart.add_group("a", &[("lz4", 1.0)]);
+        let svg = chart.render().to_string();
+        let title = tags_with(&svg, "font-weight=\"bold\"")[0];
+        assert_eq!(attribute(title, "y"), TITLE_FONT_SIZE);
+        assert!(svg.contains("Throughput"));
+    }
+
+    #[test]
+    fn contact_sheet_has_a_tile_per_bench() {
+        let name_to_benches = group(vec![
+            bench("Compress", "lz4", 1000, 1.0),
+            bench("Decompress", "lz4", 1000, 2.0),
+            bench("Hash", "lz4", 1000, 3.0),
+        ]);
+        let charts: Vec<Chart> = split_by_bench(&name_to_benches)
+            .values()
+            .map(|benches| {
+                chart(
+                    benches,
+                    &LabelOptions::default(),
+                    GroupBarOptions::default(),
+                )
+            })
+            .collect();
+        let svg = render_contact_sheet(&charts, 2).to_string();
+        let tiles: Vec<&str> = ["translate(0,0)", "translate(800,0)", "translate(0,600)"]
+            .into_iter()
+            .filter(|tile| svg.contains(&format!("<g transform=\"{}\">", tile)))
+            .collect();
+        assert_eq!(tiles.len(), 3);
+        assert!(!svg.contains("translate(800,600)"));
+        assert!(svg.contains("viewBox=\"0 0 1600 1200\""));
+    }
+
+    #[test]
+    fn inside_labels_contrast_with_the_bar() {
+        assert_eq!(contrasting_text_color("#FFCF56"), "#000000");
+        assert_eq!(contrasting_text_color("#1B1B3A"), "#FFFFFF");
+
+        let options = GroupBarOptions {
+            data_labels: DataLabels::Inside,
+            ..Default::default()
+        };
+        let mut chart = Chart::new("test", options);
+        chart.add_group("a", &[("lz4", 2.0), ("zstd", 1.5)]);
+        chart.groups[0].bars[1].color = "#1B1B3A".to_string();
+        let svg = chart.render().to_string();
+        let labels = tags_with(&svg, "dominant-baseline=\"central\"");
+        assert_eq!(labels.len(), 2);
+        assert!(labels[0].contains("fill=\"#000000\""));
+        assert!(labels[1].contains("fill=\"#FFFFFF\""));
+    }
+
+    #[test]
+    fn bars_show_their_text_label() {
+        let mut lz4 = bench("Compress", "lz4", 1000, 2.0);
+        lz4.bar_label = Some("abc123".to_string());
+        let name_to_benches = group(vec![lz4, bench("Compress", "zstd", 1000, 1.0)]);
+        let options = GroupBarOptions {
+            show_bar_labels: true,
+            ..Default::default()
+        };
+        let chart = chart(&name_to_benches, &LabelOptions::default(), options);
+        let svg = chart.render().to_string();
+        assert!(svg.contains("abc123"));
+
+        let mut options = chart.options.clone();
+        options.show_bar_labels = false;
+        let chart = Chart { options, ..chart };
+        assert!(!chart.render().to_string().contains("abc123"));
+    }
+
+    #[test]
+    fn humanize_bytes_in_si_and_binary_units() {
+        assert_eq!(humanize_bytes(9991663, ByteUnit::Si), "9.99 MB");
+        assert_eq!(humanize_bytes(9991663, ByteUnit::Binary), "9.53 MiB");
+        assert_eq!(humanize_bytes(725, ByteUnit::Si), "725 B");
+        assert_eq!(humanize_bytes(150_000, ByteUnit::Si), "150 KB");
+        // the dataset sizes keep their names
+        assert_eq!(num_bytes_to_name(9991663, ByteUnit::Binary), "10Mb Dickens");
+        assert_eq!(num_bytes_to_name(9991664, ByteUnit::Binary), "9.53 MiB");
+    }
+
+    #[test]
+    fn median_marker_is_at_the_median() {
+        let options = GroupBarOptions {
+            median_marker: true,
+            ..Default::default()
+        };
+        let mut chart = Chart::new("test", options);
+        chart.add_group("a", &[("lz4", 2.0)]);
+        chart.groups[0].bars[0].median = Some(1.0);
+        let svg = chart.render().to_string();
+        let markers = tags_with(&svg, "stroke-width=\"2\"");
+        assert_eq!(markers.len(), 1);
+        // half of the 500px plot height above the x axis at 510
+        assert_eq!(attribute(markers[0], "y1"), 260.0);
+        assert_eq!(attribute(markers[0], "y2"), 260.0);
+    }
+
+    #[test]
+    fn rotation_swaps_the_document_size() {
+        for (rotate, transform, size) in [
+            (0, None, (800, 600)),
+            (90, Some("translate(600,0) rotate(90)"), (600, 800)),
+            (270, Some("translate(0,800) rotate(270)"), (600, 800)),
+        ] {
+            let root = finish_document(element::Group::new(), 800.0, 600.0, rotate).to_string();
+            let svg_tag = tags_with(&root, "xmlns")[0];
+            assert_eq!(attribute(svg_tag, "width"), size.0 as f32);
+            assert_eq!(attribute(svg_tag, "height"), size.1 as f32);
+            assert!(svg_tag.contains(&format!("viewBox=\"0 0 {} {}\"", size.0, size.1)));
+            match transform {
+                Some(transform) => {
+                    assert!(root.contains(&format!("<g transform=\"{}\"", transform)))
+                }
+                None => assert!(!root.contains("rotate(")),
+            }
+        }
+    }
+
+    #[test]
+    fn buckets_of_byte_sizes() {
+        assert_eq!(Bucketing::Pow2.bucket(0), (0, 1));
+        assert_eq!(Bucketing::Pow2.bucket(1), (1, 2));
+        assert_eq!(Bucketing::Pow2.bucket(1024), (1024, 2048));
+        assert_eq!(Bucketing::Pow2.bucket(1500), (1024, 2048));
+        assert_eq!(Bucketing::Linear(1000).bucket(999), (0, 1000));
+        assert_eq!(Bucketing::Linear(1000).bucket(1000), (1000, 2000));
+        assert_eq!(
+            "linear:0".parse::<Bucketing>(),
+            Err("invalid bucketing \"linear:0\", expected pow2 or linear:<step>".to_string())
+        );
+    }
+
+    #[test]
+    fn bucket_benches_aggregates_per_bucket() {
+        let name_to_benches = group(vec![
+            bench("Compress", "lz4", 1100, 1.0),
+            bench("Compress", "lz4", 1500, 3.0),
+            bench("Compress", "lz4", 3000, 5.0),
+        ]);
+        let buckets = bucket_benches(&name_to_benches, Bucketing::Pow2, Stat::Mean, ByteUnit::Si);
+        let groups: Vec<&str> = buckets.keys().map(|name| name.as_str()).collect();
+        assert_eq!(groups, ["Compress/1024", "Compress/2048"]);
+        let first = &buckets["Compress/1024"];
+        assert_eq!(first.len(), 1);
+        assert_eq!(first[0].gbs, 2.0);
+        assert_eq!(first[0].group_label.as_deref(), Some("1.02 KB–2.05 KB"));
+        assert_eq!(buckets["Compress/2048"][0].gbs, 5.0);
+    }
+
+    #[test]
+    fn build_and_render_a_chart_programmatically() {
+        let mut chart = Chart::new("Hashing", GroupBarOptions::default());
+        chart.add_group("small", &[("xxhash", 5.0), ("fnv", 2.0)]);
+        chart.add_group("large", &[("xxhash", 9.0), ("crc32", 3.0)]);
+        assert_eq!(chart.groups.len(), 2);
+        let legend: Vec<&str> = chart
+            .legend
+            .iter()
+            .map(|(label, _)| label.as_str())
+            .collect();
+        assert_eq!(legend, ["xxhash", "fnv", "crc32"]);
+        // a variant keeps its color in every group
+        assert_eq!(chart.groups[0].bars[0].color, chart.groups[1].bars[0].color);
+
+        let svg = chart.render().to_string();
+        assert_eq!(svg.matches("<title>").count(), 4);
+        for text in ["Hashing", "small", "large", "xxhash: 9.00", "crc32"] {
+            assert!(svg.contains(text), "{}", text);
+        }
+    }
+
+    /// Writes the `new` directory of a criterion benchmark below `root`.
+    fn write_criterion_benchmark(root: &Path, size: u32, duration_ns: f64) {
+        let dir = root.join("Compress").join(size.to_string()).join("new");
+        std::fs::create_dir_all(&dir).unwrap();
+        let benchmark = serde_json::json!({
+            "group_id": "Compress",
+            "function_id": "lz4",
+            "value_str": size.to_string(),
+            "full_id": format!("Compress/{}", size),
+            "throughput": {"Bytes": size},
+        });
+        let estimates = serde_json::json!({
+            "mean": {"point_estimate": duration_ns},
+            "median": {"point_estimate": duration_ns},
+        });
+        std::fs::write(dir.join("benchmark.json"), benchmark.to_string()).unwrap();
+        std::fs::write(dir.join("estimates.json"), estimates.to_string()).unwrap();
+    }
+
+    #[test]
+    fn compare_two_criterion_dirs() {
+        let root = std::env::temp_dir().join("grouped_bar_chart_compare_dirs");
+        let _ = std::fs::remove_dir_all(&root);
+        let (old_dir, new_dir) = (root.join("old"), root.join("new"));
+        write_criterion_benchmark(&old_dir, 1000, 1000.0);
+        write_criterion_benchmark(&old_dir, 2000, 1000.0);
+        write_criterion_benchmark(&new_dir, 1000, 500.0);
+        // criterion's html report must not be picked up as a benchmark
+        std::fs::create_dir_all(new_dir.join("report").join("new")).unwrap();
+
+        let mut warnings = Warnings::default();
+        let groups = load_criterion_dirs(
+            old_dir.to_str().unwrap(),
+            new_dir.to_str().unwrap(),
+            &mut warnings,
+        );
+        let keys: Vec<&str> = groups.keys().map(String::as_str).collect();
+        assert_eq!(keys, ["Compress/1000", "Compress/2000"]);
+
+        let both: Vec<(&str, f64)> = groups["Compress/1000"]
+            .iter()
+            .map(|bench| (bench.variant.as_str(), bench.gbs))
+            .collect();
+        assert_eq!(both, [("old", 1.0), ("new", 2.0)]);
+
+        let only_old = &groups["Compress/2000"];
+        assert_eq!(only_old.len(), 1);
+        assert_eq!(only_old[0].variant, "old");
+        assert_eq!(warnings.entries.len(), 1);
+        assert_eq!(warnings.entries[0].kind, "missing_benchmark");
+        assert!(warnings.entries[0]
+            .message
+            .starts_with("Compress/2000 is only in"));
+    }
+
+    #[test]
+    fn delta_label_follows_the_delta_position() {
+        // bars at x 90, 113 and 136, 20px wide: the highest is the first, the group is
+        // centered at 123
+        for (delta_position, x, y) in [
+            (DeltaPosition::AboveMax, 100.0, 0.0),
+            (DeltaPosition::AboveGroup, 123.0, 0.0),
+            (DeltaPosition::Top, 123.0, 20.0),
+        ] {
+            let options = GroupBarOptions {
+                print_delta: true,
+                delta_position,
+                ..Default::default()
+            };
+            let mut chart = Chart::new("test", options);
+            chart.add_group("a", &[("lz4", 3.0), ("zstd", 1.0), ("snappy", 2.0)]);
+            let svg = chart.render().to_string();
+            let delta = tags_with(&svg, "+200.00%");
+            assert_eq!(delta.len(), 1);
+            assert_eq!(attribute(delta[0], "x"), x, "{:?}", delta_position);
+            assert_eq!(attribute(delta[0], "y"), y, "{:?}", delta_position);
+        }
+    }
+
+    #[test]
+    fn values_below_the_min_value_are_dropped() {
+        let mut name_to_benches = group(vec![
+            bench("Compress", "lz4", 1000, 2.0),
+            bench("Compress", "zstd", 1000, 0.5),
+            bench("Compress", "lz4", 2000, 0.1),
+            bench("Compress", "zstd", 2000, 0.2),
+        ]);
+        let mut warnings = Warnings::default();
+        drop_below_min_value(&mut name_to_benches, 1.0, &mut warnings);
+        let keys: Vec<&str> = name_to_benches.keys().map(String::as_str).collect();
+        assert_eq!(keys, ["Compress/1000"]);
+        let variants: Vec<&str> = name_to_benches["Compress/1000"]
+            .iter()
+            .map(|bench| bench.variant.as_str())
+            .collect();
+        assert_eq!(variants, ["lz4"]);
+        assert_eq!(warnings.entries.len(), 3);
+        assert!(warnings
+            .entries
+            .iter()
+            .all(|warning| warning.kind == "below_min_value"));
+    }
+
+    /// Runs `load_data` on the given lines of criterion JSON.
+    fn load_lines(
+        test_name: &str,
+        lines: &[serde_json::Value],
+        load_options: &LoadOptions,
+    ) -> (BTreeMap<String, Vec<BenchData>>, Warnings) {
+        let path = std::env::temp_dir().join(format!("grouped_bar_chart_{}.json", test_name));
+        let lines: Vec<String> = lines.iter().map(|line| line.to_string()).collect();
+        std::fs::write(&path, lines.join("\n")).unwrap();
+        let mut warnings = Warnings::default();
+        let groups = load_data(path.to_str().unwrap(), load_options, &mut warnings);
+        (groups, warnings)
+    }
+
+    /// A `benchmark-complete` line of the benchmark `id`, taking 1000ns per iteration.
+    fn criterion_line(id: &str) -> serde_json::Value {
+        serde_json::json!({
+            "reason": "benchmark-complete",
+            "id": id,
+            "iteration_count": [10, 20, 30],
+            "measured_values": [10000.0, 20000.0, 30000.0],
+            "typical": {"estimate": 1000.0},
+        })
+    }
+
+    #[test]
+    fn rich_tooltips_show_samples_and_iterations() {
+        let (name_to_benches, _) = load_lines(
+            "rich_tooltips",
+            &[criterion_line("Compress/lz4/1000")],
+            &LoadOptions::default(),
+        );
+        let bench = &name_to_benches["Compress/1000"][0];
+        assert_eq!(bench.samples, Some(3));
+        assert_eq!(bench.iterations, Some(60));
+
+        let tooltip = |rich_tooltips| {
+            let label_options = LabelOptions {
+                rich_tooltips,
+                ..Default::default()
+            };
+            let chart = chart(&name_to_benches, &label_options, GroupBarOptions::default());
+            chart.groups[0].bars[0].tooltip.clone().unwrap()
+        };
+        assert_eq!(tooltip(false), "lz4 1.00 KB: 1.00");
+        assert_eq!(
+            tooltip(true),
+            "lz4 1.00 KB: 1.00\nCV 0.0%\n3 samples\n60 iterations"
+        );
+    }
+
+    #[test]
+    fn every_bar_has_a_title() {
+        let name_to_benches = group(vec![
+            bench("Compress", "lz4", 1000, 2.0),
+            bench("Compress", "zstd", 1000, 1.0),
+            bench("Compress", "lz4", 2000, 3.0),
+        ]);
+        let chart = chart(
+            &name_to_benches,
+            &LabelOptions::default(),
+            GroupBarOptions::default(),
+        );
+        let svg = chart.render().to_string();
+        assert_eq!(svg.matches("<title>").count(), 3);
+        assert!(svg.contains("zstd 1.00 KB: 1.00"));
+    }
+
+    #[test]
+    fn value_desc_lists_the_fastest_variant_first() {
+        let name_to_benches = group(vec![
+            bench("Compress", "lz4", 1000, 2.0),
+            bench("Compress", "snappy", 1000, 1.0),
+            bench("Compress", "zstd", 1000, 4.0),
+            bench("Compress", "lz4", 2000, 2.0),
+            bench("Compress", "snappy", 2000, 5.0),
+            bench("Compress", "zstd", 2000, 4.0),
+        ]);
+        let legend = |legend_sort| {
+            let label_options = LabelOptions {
+                legend_sort,
+                ..Default::default()
+            };
+            chart(&name_to_benches, &label_options, GroupBarOptions::default())
+                .legend
+                .into_iter()
+                .map(|(label, _)| label)
+                .collect::<Vec<_>>()
+        };
+        // means: lz4 2.0, snappy 3.0, zstd 4.0
+        assert_eq!(legend(LegendSort::ValueDesc), ["zstd", "snappy", "lz4"]);
+        assert_eq!(legend(LegendSort::ValueAsc), ["lz4", "snappy", "zstd"]);
+    }
+
+    #[test]
+    fn a11y_table_has_a_cell_per_group_and_variant() {
+        let name_to_benches = group(vec![
+            bench("Compress", "lz4", 1000, 2.0),
+            bench("Compress", "zstd", 1000, 1.0),
+            bench("Compress", "lz4", 2000, 3.0),
+            bench("Compress", "zstd", 2000, 1.5),
+            bench("Compress", "lz4", 3000, 4.0),
+        ]);
+        let chart = chart(
+            &name_to_benches,
+            &LabelOptions::default(),
+            GroupBarOptions::default(),
+        );
+        let svg = chart.render().to_string();
+        let html = render_html("test", &svg, std::slice::from_ref(&chart), true, false);
+        assert_eq!(html.matches("<table class=\"visually-hidden\">").count(), 1);
+        // 3 groups by 2 variants, the missing zstd bar is an empty cell
+        assert_eq!(html.matches("<td>").count(), 6);
+        assert_eq!(html.matches("<td></td>").count(), 1);
+        assert_eq!(html.matches("<th scope=\"col\">").count(), 3);
+        assert_eq!(html.matches("<th scope=\"row\">").count(), 3);
+
+        let html = render_html("test", &svg, std::slice::from_ref(&chart), false, false);
+        assert!(!html.contains("<table"));
+    }
+
+    #[test]
+    fn heat_colors_the_highest_bar_warm() {
+        assert_eq!(heat_color("#0000FF", "#FF0000", 0.0), "#0000FF");
+        assert_eq!(heat_color("#0000FF", "#FF0000", 1.0), "#FF0000");
+        assert_eq!(heat_color("#0000FF", "#FF0000", 0.5), "#800080");
+
+        let mut chart = Chart::new("test", GroupBarOptions::default());
+        chart.add_group("a", &[("lz4", 4.0), ("zstd", 2.0)]);
+        chart.add_group("b", &[("lz4", 1.0), ("zstd", 0.0)]);
+        chart.apply_heat("#0000FF", "#FF0000");
+        let colors: Vec<&str> = chart
+            .groups
+            .iter()
+            .flat_map(|group| &group.bars)
+            .map(|bar| bar.color.as_str())
+            .collect();
+        assert_eq!(colors, ["#FF0000", "#800080", "#4000BF", "#0000FF"]);
+        assert_eq!(chart.legend[0], ("4.00".to_string(), "#FF0000".to_string()));
+    }
+
+    #[test]
+    fn small_groups_collapse_into_other() {
+        let mut name_to_benches = group(vec![
+            bench("Compress", "lz4", 1000, 5.0),
+            bench("Compress", "zstd", 1000, 4.0),
+            bench("Compress", "lz4", 2000, 0.5),
+            bench("Compress", "zstd", 2000, 0.25),
+            bench("Compress", "lz4", 3000, 0.25),
+            bench("Compress", "zstd", 3000, 0.5),
+        ]);
+        let variant_to_color = build_variant_to_color(&name_to_benches, ColorMode::Ordered);
+        let other_group = merge_into_other(&mut name_to_benches, 1.0, Stat::Sum);
+        assert_eq!(other_group.as_deref(), Some("other"));
+        let keys: Vec<&str> = name_to_benches.keys().map(String::as_str).collect();
+        assert_eq!(keys, ["Compress/1000", "other"]);
+        let other: Vec<(&str, f64)> = name_to_benches["other"]
+            .iter()
+            .map(|bench| (bench.variant.as_str(), bench.gbs))
+            .collect();
+        assert_eq!(other, [("lz4", 0.75), ("zstd", 0.75)]);
+
+        let label_options = LabelOptions {
+            other_group,
+            ..Default::default()
+        };
+        let chart = build_chart(
+            "test",
+            &name_to_benches,
+            &variant_to_color,
+            &label_options,
+            GroupBarOptions::default(),
+            &mut Warnings::default(),
+        );
+        assert_eq!(chart.groups[1].label, "other");
+        assert!(chart.groups[1]
+            .bars
+            .iter()
+            .all(|bar| bar.color == OTHER_GROUP_COLOR));
+        assert_eq!(chart.groups[0].bars[0].color, variant_to_color["lz4"]);
+    }
+
+    #[test]
+    fn merging_keeps_an_existing_other_group() {
+        let mut name_to_benches = group(vec![
+            bench("Compress", "lz4", 2000, 0.5),
+            bench("Compress", "lz4", 3000, 0.25),
+        ]);
+        let mut other = bench("Compress", "lz4", 1000, 5.0);
+        other.group_name = "other".to_string();
+        name_to_benches.insert("other".to_string(), vec![other]);
+
+        let other_group = merge_into_other(&mut name_to_benches, 1.0, Stat::Mean);
+        assert_eq!(other_group.as_deref(), Some("other #2"));
+        assert_eq!(name_to_benches["other"][0].gbs, 5.0);
+        assert_eq!(name_to_benches["other #2"][0].gbs, 0.375);
+    }
+
+    #[test]
+    fn marker_is_drawn_before_the_named_group() {
+        let options = GroupBarOptions {
+            markers: vec![("b".to_string(), "cache".to_string())],
+            ..Default::default()
+        };
+        let mut chart = Chart::new("test", options);
+        chart.add_group("a", &[("lz4", 1.0), ("zstd", 3.0)]);
+        chart.add_group("b", &[("lz4", 1.0), ("zstd", 3.0)]);
+        let svg = chart.render().to_string();
+        let lines = tags_with(&svg, "stroke-dasharray");
+        assert_eq!(lines.len(), 1);
+        // halfway between the last bar of `a`, ending at 133, and the first bar of `b` at 440
+        assert_eq!(attribute(lines[0], "x1"), 286.5);
+        assert_eq!(attribute(lines[0], "x2"), 286.5);
+        assert!(svg.contains("cache"));
+    }
+
+    #[test]
+    fn stroke_widths_scale_with_the_stroke_scale() {
+        let render = |stroke_scale| {
+            let options = GroupBarOptions {
+                stroke_scale,
+                median_marker: true,
+                markers: vec![("a".to_string(), "cache".to_string())],
+                ..Default::default()
+            };
+            let mut chart = Chart::new("test", options);
+            chart.add_group("a", &[("lz4", 2.0)]);
+            chart.groups[0].bars[0].median = Some(1.0);
+            chart.render().to_string()
+        };
+        // the median marker is the only line starting at the bar at x 90
+        let svg = render(1.0);
+        assert!(!tags_with(&svg, "font-family")[0].contains("stroke-width"));
+        assert_eq!(
+            attribute(tags_with(&svg, "x1=\"90\"")[0], "stroke-width"),
+            2.0
+        );
+        assert_eq!(
+            attribute(tags_with(&svg, "stroke-dasharray")[0], "stroke-width"),
+            1.0
+        );
+
+        let svg = render(3.0);
+        // the axes and the grid inherit the width of the root group
+        assert_eq!(
+            attribute(tags_with(&svg, "font-family")[0], "stroke-width"),
+            3.0
+        );
+        assert_eq!(
+            attribute(tags_with(&svg, "x1=\"90\"")[0], "stroke-width"),
+            6.0
+        );
+        assert_eq!(
+            attribute(tags_with(&svg, "stroke-dasharray")[0], "stroke-width"),
+            3.0
+        );
+    }
+
+    #[test]
+    fn group_label_field_overrides_the_byte_label() {
+        let mut labelled = criterion_line("Compress/lz4/1000");
+        labelled["meta"] = serde_json::json!({"label": "small file"});
+        let lines = [labelled, criterion_line("Compress/lz4/2000")];
+        let load_options = LoadOptions {
+            group_label_field: Some("meta.label".to_string()),
+            ..Default::default()
+        };
+        let (name_to_benches, _) = load_lines("group_label_field", &lines, &load_options);
+        let chart = chart(
+            &name_to_benches,
+            &LabelOptions::default(),
+            GroupBarOptions::default(),
+        );
+        let labels: Vec<&str> = chart
+            .groups
+            .iter()
+            .map(|group| group.label.as_str())
+            .collect();
+        // the line without the field falls back to the number of bytes
+        assert_eq!(labels, ["small file", "2.00 KB"]);
+    }
+
+    #[test]
+    fn bytes_field_sets_the_throughput() {
+        let line = |id: &str, bytes: serde_json::Value| {
+            let mut line = criterion_line(id);
+            line["throughput"] = serde_json::json!({ "bytes": bytes });
+            line
+        };
+        let lines = [
+            line("Compress/lz4", serde_json::json!(2000)),
+            line("Compress/zstd", serde_json::json!("3000")),
+            // doesn't fit into u32, must not wrap around
+            line("Compress/snappy", serde_json::json!(1u64 << 32)),
+        ];
+        let load_options = LoadOptions {
+            bytes_field: Some("throughput.bytes".to_string()),
+            ..Default::default()
+        };
+        let (name_to_benches, warnings) = load_lines("bytes_field", &lines, &load_options);
+        let keys: Vec<&str> = name_to_benches.keys().map(String::as_str).collect();
+        assert_eq!(keys, ["Compress/2000", "Compress/3000"]);
+        // 1000ns per iteration
+        assert_eq!(name_to_benches["Compress/2000"][0].gbs, 2.0);
+        assert_eq!(name_to_benches["Compress/3000"][0].gbs, 3.0);
+        assert_eq!(warnings.entries.len(), 1);
+        assert_eq!(warnings.entries[0].kind, "missing_field");
+        assert_eq!(warnings.entries[0].line, Some(3));
+    }
+
+    #[test]
+    fn preview_prints_proportional_bars() {
+        let mut chart = Chart::new("Compress", GroupBarOptions::default());
+        chart.add_group("a", &[("lz4", 4.0), ("zstd", 2.0)]);
+        chart.add_group("b", &[("lz4", 1.0)]);
+        // 33 columns leave 20 blocks next to the variant names and the values
+        let preview = render_preview(&chart, 33);
+        let lines: Vec<&str> = preview.lines().collect();
+        assert_eq!(lines.len(), 6);
+        assert_eq!(lines[0], "Compress");
+        assert_eq!(lines[1], "a");
+        assert_eq!(lines[2], format!("  lz4  |{} 4.00", "█".repeat(20)));
+        assert_eq!(lines[3], format!("  zstd |{} 2.00", "█".repeat(10)));
+        assert_eq!(lines[4], "b");
+        assert_eq!(lines[5], format!("  lz4  |{} 1.00", "█".repeat(5)));
+        assert!(lines.iter().all(|line| line.chars().count() <= 33));
+    }
+
+    #[test]
+    fn tick_text_follows_the_axis_number_format() {
+        let ticks = |axis_number_format| {
+            let options = GroupBarOptions {
+                axis_number_format,
+                ..Default::default()
+            };
+            let mut chart = Chart::new("test", options);
+            chart.add_group("a", &[("lz4", 6_000_000.0)]);
+            let svg = chart.render().to_string();
+            tags_with(&svg, "direction=\"rtl\"")
+                .iter()
+                .map(|tag| tag[tag.find('>').unwrap() + 1..].trim().to_string())
+                .collect::<Vec<_>>()
+        };
+        let auto = ticks(AxisNumberFormat::Auto);
+        assert_eq!(auto.len(), 7);
+        assert_eq!(auto[1], "1000000");
+        assert_eq!(auto[6], "6000000");
+        assert_eq!(ticks(AxisNumberFormat::Fixed(1))[6], "6000000.0");
+        assert_eq!(ticks(AxisNumberFormat::Scientific)[6], "6.0e6");
+        assert_eq!(ticks(AxisNumberFormat::Compact)[6], "6M");
+
+        assert_eq!("fixed:2".parse(), Ok(AxisNumberFormat::Fixed(2)));
+        assert!("fixed:x".parse::<AxisNumberFormat>().is_err());
+    }
+
+    #[test]
+    fn overlay_line_passes_through_the_expected_values() {
+        let mut chart = Chart::new("test", GroupBarOptions::default());
+        chart.add_group("a", &[("lz4", 2.0), ("zstd", 4.0)]);
+        chart.add_group("b", &[("lz4", 2.0), ("zstd", 4.0)]);
+        chart.add_group("c", &[("lz4", 2.0), ("zstd", 4.0)]);
+        chart.set_overlay_line(&BTreeMap::from([
+            ("a".to_string(), 1.0),
+            ("b".to_string(), 3.0),
+        ]));
+        let svg = chart.render().to_string();
+        let polyline = tags_with(&svg, "polyline")[0];
+        // at the center of the groups, 500px for the max of 4 above the x axis at 510. `c` has
+        // no expected value.
+        assert!(
+            polyline.contains("points=\"111.5,385 344.8333,135\""),
+            "{}",
+            polyline
+        );
+    }
+
+    #[test]
+    fn hash_colors_dont_depend_on_the_other_variants() {
+        let colors = |variants: &[&str]| {
+            let benches = variants
+                .iter()
+                .map(|variant| bench("Compress", variant, 1000, 1.0))
+                .collect();
+            build_variant_to_color(&group(benches), ColorMode::Hash)
+        };
+        let alone = colors(&["zstd"]);
+        for others in [&["lz4", "zstd"][..], &["brotli", "xz", "zstd"]] {
+            assert_eq!(colors(others)["zstd"], alone["zstd"], "{:?}", others);
+        }
+        assert_eq!(
+            alone["zstd"],
+            palette_color(fnv1a("zstd") as usize % PALETTE.len())
+        );
+        // `snappy` hashes to the color of `zstd` and gets the next free one
+        let colliding = colors(&["snappy", "zstd"]);
+        assert_ne!(colliding["snappy"], colliding["zstd"]);
+    }
+
+    #[test]
+    fn box_geometry_matches_the_percentiles() {
+        let render = |chart_type| {
+            let options = GroupBarOptions {
+                chart_type,
+                ..Default::default()
+            };
+            let mut chart = Chart::new("test", options);
+            // zstd has no percentiles and is drawn as a bar
+            chart.add_group("a", &[("lz4", 2.0), ("zstd", 1.0)]);
+            chart.groups[0].bars[0].percentiles = Some([1.0, 1.5, 2.0, 3.0, 4.0]);
+            chart.render().to_string()
+        };
+        let svg = render(ChartType::Box);
+        // the whisker at the 95th percentile of 4 is the top of the 500px plot above 510
+        let rect = tags_with(&svg, "fill=\"#FFCF56\"")[0];
+        assert_eq!(attribute(rect, "x"), 90.0);
+        assert_eq!(attribute(rect, "width"), 20.0);
+        assert_eq!(attribute(rect, "y"), 135.0);
+        assert_eq!(attribute(rect, "height"), 187.5);
+        let caps: Vec<f32> = tags_with(&svg, "x1=\"95\"")
+            .iter()
+            .map(|cap| {
+                assert_eq!(attribute(cap, "x2"), 105.0);
+                attribute(cap, "y1")
+            })
+            .collect();
+        assert_eq!(caps, [385.0, 10.0]);
+        let median = tags_with(&svg, "x1=\"90\"")[0];
+        assert_eq!(attribute(median, "y1"), 260.0);
+        let bar = tags_with(&svg, "fill=\"#EDEAD0\"")[0];
+        assert_eq!(attribute(bar, "height"), 125.0);
+
+        // bars ignore the percentiles for the scale
+        let svg = render(ChartType::Bar);
+        let rect = tags_with(&svg, "fill=\"#FFCF56\"")[0];
+        assert_eq!(attribute(rect, "height"), 500.0);
+    }
+
+    #[test]
+    fn label_every_nth_thins_the_group_labels() {
+        let labels = |label_every_nth| {
+            let options = GroupBarOptions {
+                label_every_nth,
+                ..Default::default()
+            };
+            let mut chart = Chart::new("test", options);
+            for idx in 0..10 {
+                chart.add_group(&format!("group{}", idx), &[("lz4", 1.0), ("zstd", 2.0)]);
+            }
+            let svg = chart.render().to_string();
+            tags_with(&svg, "text-anchor=\"left\"")
+                .iter()
+                .map(|tag| tag[tag.find('>').unwrap() + 1..].trim().to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(labels(1).len(), 10);
+        assert_eq!(
+            labels(2),
+            ["group0", "group2", "group4", "group6", "group8"]
+        );
+    }
+
+    #[test]
+    fn single_variant_groups_are_drawn_as_a_plain_bar_chart() {
+        let mut chart = Chart::new("test", GroupBarOptions::default());
+        for (idx, label) in ["a", "b", "c", "d"].into_iter().enumerate() {
+            chart.add_group(label, &[("lz4", idx as f32 + 1.0)]);
+        }
+        let layout = ChartLayout::new(&chart.options, &chart.groups);
+        // 700px for 4 groups, the bars use 70% of each group instead of the 20px cap
+        assert_eq!(layout.group_width, 175.0);
+        assert_eq!(layout.bar_width, 122.5);
+
+        let svg = chart.render().to_string();
+        let bars = tags_with(&svg, "width=\"122.5\"");
+        assert_eq!(bars.len(), 4);
+        let xs: Vec<f32> = bars.iter().map(|bar| attribute(bar, "x")).collect();
+        assert_eq!(xs, [90.0, 265.0, 440.0, 615.0]);
+    }
+
+    #[test]
+    fn bar_axis_ticks_stay_below_the_max() {
+        assert_eq!(
+            bar_axis_ticks(100.0, 8),
+            [0.0, 20.0, 40.0, 60.0, 80.0, 100.0]
+        );
+        for max in [1.0, 3.0, 7.5, 99.0, 12345.0] {
+            let ticks = bar_axis_ticks(max, 8);
+            assert!(ticks.iter().all(|tick| *tick <= max), "{} {:?}", max, ticks);
+            assert_eq!(ticks[0], 0.0);
+        }
+    }
+
+    #[test]
+    fn separators_between_the_groups() {
+        let separators = |group_separators| {
+            let options = GroupBarOptions {
+                group_separators,
+                ..Default::default()
+            };
+            let mut chart = Chart::new("test", options);
+            for label in ["a", "b", "c", "d"] {
+                chart.add_group(label, &[("lz4", 1.0), ("zstd", 2.0)]);
+            }
+            let svg = chart.render().to_string();
+            tags_with(&svg, "stroke-opacity=\"0.5\"")
+                .iter()
+                .map(|separator| attribute(separator, "x1"))
+                .collect::<Vec<_>>()
+        };
+        assert!(separators(false).is_empty());
+        // halfway between the end of a group, 43px after its start, and the next group 175px
+        // after it
+        assert_eq!(separators(true), [199.0, 374.0, 549.0]);
+    }
+
+    #[test]
+    fn every_bar_grows_from_the_x_axis() {
+        let options = GroupBarOptions {
+            animate: true,
+            ..Default::default()
+        };
+        let mut chart = Chart::new("test", options);
+        chart.add_group("a", &[("lz4", 1.0), ("zstd", 2.0)]);
+        chart.add_group("b", &[("lz4", 2.0)]);
+        let svg = chart.render().to_string();
+        let bars: Vec<&str> = svg
+            .split("<rect")
+            .filter(|rect| rect.contains("<title>"))
+            .map(|rect| &rect[..rect.find("</rect>").unwrap()])
+            .collect();
+        assert_eq!(bars.len(), 3);
+        for bar in &bars {
+            let animations = tags_with(bar, "animate ");
+            assert_eq!(animations.len(), 2, "{}", bar);
+            assert!(animations[0].contains("attributeName=\"height\""));
+            assert_eq!(attribute(animations[0], "from"), 0.0);
+            assert_eq!(attribute(animations[0], "to"), attribute(bar, "height"));
+            assert!(animations[1].contains("attributeName=\"y\""));
+            assert_eq!(attribute(animations[1], "from"), 510.0);
+            assert_eq!(attribute(animations[1], "to"), attribute(bar, "y"));
+        }
+        assert!(tags_with(bars[2], "animate ")[0].contains("dur=\"700ms\""));
+    }
+
+    #[test]
+    fn reason_filter_selects_the_lines() {
+        let mut custom = criterion_line("Compress/lz4/1000");
+        custom["reason"] = serde_json::json!("my-bench");
+        let mut missing = criterion_line("Compress/zstd/1000");
+        missing.as_object_mut().unwrap().remove("reason");
+        let lines = [custom, missing, criterion_line("Compress/snappy/1000")];
+        let variants = |load_options: &LoadOptions| {
+            let (name_to_benches, warnings) = load_lines("reason_filter", &lines, load_options);
+            let variants: Vec<String> = name_to_benches
+                .values()
+                .flatten()
+                .map(|bench| bench.variant.to_string())
+                .collect();
+            (variants, warnings.entries.len())
+        };
+        // the line without a reason is skipped with a warning
+        assert_eq!(
+            variants(&LoadOptions::default()),
+            (vec!["snappy".to_string()], 1)
+        );
+        let custom_reason = LoadOptions {
+            reason_filter: "my-bench".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(variants(&custom_reason), (vec!["lz4".to_string()], 1));
+        let any_reason = LoadOptions {
+            reason_filter: "*".to_string(),
+            accept_missing_reason: true,
+            ..Default::default()
+        };
+        assert_eq!(variants(&any_reason).0, ["lz4", "zstd", "snappy"]);
+    }
+
+    #[test]
+    fn long_labels_are_abbreviated_but_not_their_tooltip() {
+        assert_eq!(abbreviate("lz4_flex_rust", 10), "lz4_f…rust");
+        assert_eq!(abbreviate("lz4_flex_rust", 10).chars().count(), 10);
+        assert_eq!(abbreviate("lz4", 10), "lz4");
+
+        let name_to_benches = group(vec![
+            bench("Compress", "lz4_flex_rust", 1000, 2.0),
+            bench("Compress", "lz4_flex_rust", 2000, 3.0),
+        ]);
+        let label_options = LabelOptions {
+            legend_range: true,
+            ..Default::default()
+        };
+        let options = GroupBarOptions {
+            max_label_length: Some(10),
+            ..Default::default()
+        };
+        let chart = chart(&name_to_benches, &label_options, options);
+        // the range is appended to the abbreviated name
+        assert_eq!(chart.legend[0].0, "lz4_f…rust [2.00–3.00]");
+        let svg = chart.render().to_string();
+        assert!(svg.contains("lz4_f…rust [2.00–3.00]"));
+        assert!(svg.contains("lz4_flex_rust 1.00 KB: 2.00"));
+    }
+
+    #[test]
+    fn noisy_bars_get_a_badge() {
+        let options = GroupBarOptions {
+            cv_threshold: Some(0.05),
+            ..Default::default()
+        };
+        let mut chart = Chart::new("test", options);
+        chart.add_group("a", &[("lz4", 1.0), ("zstd", 2.0), ("snappy", 2.0)]);
+        chart.groups[0].bars[0].cv = Some(0.1);
+        chart.groups[0].bars[1].cv = Some(0.01);
+        let svg = chart.render().to_string();
+        let badges = tags_with(&svg, "circle ");
+        assert_eq!(badges.len(), 1);
+        // centered above the noisy bar at x 90
+        assert_eq!(attribute(badges[0], "cx"), 100.0);
+        assert!(svg.contains("noisy, CV 10.0%"));
+
+        chart.options.cv_threshold = None;
+        assert!(!chart.render().to_string().contains("<circle"));
+    }
+
+    #[test]
+    fn variant_order_file_orders_bars_and_legend() {
+        let order_file = std::env::temp_dir().join("grouped_bar_chart_variant_order.txt");
+        std::fs::write(&order_file, "# fastest first\nzstd\n\nlz4\n").unwrap();
+        let variant_order = load_variant_order(order_file.to_str().unwrap());
+        assert_eq!(variant_order, ["zstd", "lz4"]);
+
+        let mut name_to_benches = group(vec![
+            bench("Compress", "snappy", 1000, 1.0),
+            bench("Compress", "lz4", 1000, 2.0),
+            bench("Compress", "brotli", 1000, 3.0),
+            bench("Compress", "zstd", 1000, 4.0),
+        ]);
+        order_variants(&mut name_to_benches, &variant_order);
+        let label_options = LabelOptions {
+            variant_order,
+            ..Default::default()
+        };
+        let chart = chart(&name_to_benches, &label_options, GroupBarOptions::default());
+        // unlisted variants follow in alphabetical order
+        let expected = ["zstd", "lz4", "brotli", "snappy"];
+        let bars: Vec<&str> = chart.groups[0]
+            .bars
+            .iter()
+            .map(|bar| bar.variant.as_str())
+            .collect();
+        assert_eq!(bars, expected);
+        let legend: Vec<&str> = chart
+            .legend
+            .iter()
+            .map(|(label, _)| label.as_str())
+            .collect();
+        assert_eq!(legend, expected);
+    }
+
+    #[test]
+    fn css_mode_references_defined_classes() {
+        let options = GroupBarOptions {
+            animate: true,
+            ..Default::default()
+        };
+        let mut chart = Chart::new("test", options);
+        chart.add_group("a", &[("lz4", 1.0), ("zstd", 2.0)]);
+        let svg = move_styles_to_css(&chart.render().to_string());
+
+        let style = &svg[svg.find("<style>").unwrap()..svg.find("</style>").unwrap()];
+        assert!(style.contains("fill: #FFCF56;"));
+        assert!(style.contains("font-size: 12px;"));
+        let classes: Vec<&str> = svg
+            .split(" class=\"")
+            .skip(1)
+            .map(|rest| &rest[..rest.find('"').unwrap()])
+            .collect();
+        assert!(!classes.is_empty());
+        for class in classes {
+            assert!(style.contains(&format!(".{} {{", class)), "{}", class);
+        }
+        // only the fill of the animations is left as an attribute
+        let body = &svg[svg.find("</style>").unwrap()..];
+        for attribute in ["stroke=", "font-family=", "font-size="] {
+            assert!(!body.contains(attribute), "{}", attribute);
+        }
+        assert_eq!(
+            body.matches(" fill=").count(),
+            body.matches("<animate ").count()
+        );
+    }
+
+    #[test]
+    fn max_rows_stops_at_the_limit() {
+        let mut other_reason = criterion_line("Compress/lz4/1000");
+        other_reason["reason"] = serde_json::json!("group-complete");
+        let lines = [
+            criterion_line("Compress/lz4/1000"),
+            other_reason,
+            criterion_line("Hash/lz4/1000"),
+            criterion_line("Compress/zstd/1000"),
+            criterion_line("Compress/snappy/1000"),
+            criterion_line("Compress/brotli/1000"),
+        ];
+        let load = |max_rows| {
+            let load_options = LoadOptions {
+                max_rows: Some(max_rows),
+                id_denylist: vec!["Hash/*".to_string()],
+                ..Default::default()
+            };
+            let (name_to_benches, warnings) = load_lines("max_rows", &lines, &load_options);
+            let variants: Vec<String> = name_to_benches
+                .values()
+                .flatten()
+                .map(|bench| bench.variant.to_string())
+                .collect();
+            (variants, warnings.entries)
+        };
+        // the skipped lines don't count
+        let (variants, warnings) = load(3);
+        assert_eq!(variants, ["lz4", "zstd", "snappy"]);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].kind, "max_rows");
+        assert_eq!(warnings[0].line, Some(6));
+        // no warning if the limit isn't reached
+        let (variants, warnings) = load(4);
+        assert_eq!(variants.len(), 4);
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn deltas_vs_previous_restart_at_every_bench() {
+        let name_to_benches = group(vec![
+            bench("Compress", "lz4", 1000, 1.0),
+            bench("Compress", "zstd", 1000, 3.0),
+            bench("Compress", "lz4", 2000, 3.0),
+            bench("Compress", "zstd", 2000, 5.0),
+            bench("Decompress", "lz4", 1000, 8.0),
+            bench("Decompress", "lz4", 2000, 6.0),
+        ]);
+        let chart = chart(
+            &name_to_benches,
+            &LabelOptions::default(),
+            GroupBarOptions::default(),
+        );
+        // the means are 2, 4, 8 and 6
+        assert_eq!(
+            deltas_vs_previous(&chart.groups),
+            [None, Some(100.0), None, Some(-25.0)]
+        );
+
+        let chart = Chart {
+            options: GroupBarOptions {
+                delta_vs_previous: true,
+                ..Default::default()
+            },
+            ..chart
+        };
+        let svg = chart.render().to_string();
+        assert!(svg.contains("+100.00%"));
+        assert!(svg.contains("-25.00%"));
+        assert!(!svg.contains("+33.33%"));
+    }
+
+    #[test]
+    fn render_hook_draws_with_the_layout() {
+        let mut chart = Chart::new("test", GroupBarOptions::default());
+        chart.add_group("a", &[("lz4", 1.0), ("zstd", 2.0)]);
+        let mut calls = 0;
+        let svg = chart
+            .render_with_hook(|doc, layout| {
+                calls += 1;
+                doc.append(
+                    Rectangle::new()
+                        .set("id", "watermark")
+                        .set("x", layout.bar_x(0, 1))
+                        .set("y", layout.y_for_value(layout.max_value))
+                        .set("width", layout.bar_width)
+                        .set("height", 5),
+                );
+            })
+            .to_string();
+        assert_eq!(calls, 1);
+        let watermark = tags_with(&svg, "id=\"watermark\"");
+        assert_eq!(watermark.len(), 1);
+        // on top of the highest bar
+        assert_eq!(attribute(watermark[0], "x"), 113.0);
+        assert_eq!(attribute(watermark[0], "y"), 10.0);
+        assert_eq!(attribute(watermark[0], "width"), 20.0);
+    }
+
+    #[test]
+    fn compact_numbers() {
+        assert_eq!(compact_number(1_500_000.0), "1.5M");
+        assert_eq!(compact_number(999_999.0), "1M");
+        assert_eq!(compact_number(999_000.0), "999k");
+        assert_eq!(compact_number(-2_000.0), "-2k");
+        assert_eq!(compact_number(3e12), "3T");
+        assert_eq!(compact_number(999.0), "999");
+        assert_eq!(compact_number(0.5), "0.5");
+    }
+
+    #[test]
+    fn background_gradient_is_defined_and_used() {
+        let svg = new_root_group(800.0, 600.0, None).to_string();
+        assert!(!svg.contains("<linearGradient"));
+        assert!(svg.contains("fill=\"#333333\""));
+
+        let gradient = ("#444444".to_string(), "#222222".to_string());
+        let svg = new_root_group(800.0, 600.0, Some(&gradient)).to_string();
+        let definition = tags_with(&svg, "linearGradient ");
+        assert_eq!(definition.len(), 1);
+        assert!(definition[0].contains("id=\"background-gradient\""));
+        let stops = tags_with(&svg, "stop ");
+        assert_eq!(stops.len(), 2);
+        assert!(stops[0].contains("stop-color=\"#444444\""));
+        assert!(stops[1].contains("stop-color=\"#222222\""));
+        // the definitions come before the background using them
+        let background = tags_with(&svg, "width=\"800\"");
+        assert_eq!(background.len(), 1);
+        assert!(background[0].contains("fill=\"url(#background-gradient)\""));
+        assert!(svg.find("<defs").unwrap() < svg.find("url(#background-gradient)").unwrap());
+    }
+
+    #[test]
+    fn image_map_has_an_area_per_bar() {
+        let mut chart = Chart::new("test", GroupBarOptions::default());
+        chart.add_group("a", &[("lz4", 1.0), ("zstd", 2.0)]);
+        chart.add_group("b", &[("lz4", 2.0)]);
+        let svg = chart.render().to_string();
+        let html = render_html("test", &svg, std::slice::from_ref(&chart), false, true);
+        assert!(html.contains("usemap=\"#chart-map\""));
+        assert!(html.contains("<map name=\"chart-map\">"));
+        let areas: Vec<(&str, &str, &str)> = tags_with(&html, "area ")
+            .iter()
+            .map(|area| {
+                let value = |name: &str| {
+                    let start = area.find(&format!(" {}=\"", name)).unwrap() + name.len() + 3;
+                    &area[start..start + area[start..].find('"').unwrap()]
+                };
+                (value("coords"), value("data-group"), value("data-variant"))
+            })
+            .collect();
+        assert_eq!(
+            areas,
+            [
+                ("90,260,110,510", "a", "lz4"),
+                ("113,10,133,510", "a", "zstd"),
+                ("440,10,460,510", "b", "lz4"),
+            ]
+        );
+    }
+
+    /// The hue in degrees and the lightness of a `#RRGGBB` color.
+    fn hue_and_lightness(color: &str) -> (f32, f32) {
+        let channel =
+            |idx: usize| u8::from_str_radix(&color[1 + idx * 2..3 + idx * 2], 16).unwrap() as f32;
+        let (r, g, b) = (channel(0) / 255.0, channel(1) / 255.0, channel(2) / 255.0);
+        let (max, min) = (r.max(g).max(b), r.min(g).min(b));
+        let chroma = max - min;
+        let hue = if max == r {
+            60.0 * ((g - b) / chroma).rem_euclid(6.0)
+        } else if max == g {
+            60.0 * ((b - r) / chroma + 2.0)
+        } else {
+            60.0 * ((r - g) / chroma + 4.0)
+        };
+        (hue, (max + min) / 2.0)
+    }
+
+    #[test]
+    fn variant_families_share_a_hue() {
+        let name_to_benches = group(vec![
+            bench("Compress", "lz4", 1000, 1.0),
+            bench("Compress", "zstd-1", 1000, 2.0),
+            bench("Compress", "zstd-19", 1000, 3.0),
+        ]);
+        let colors = build_family_colors(&name_to_benches, '-');
+        let (lz4, zstd_1, zstd_19) = (
+            hue_and_lightness(&colors["lz4"]),
+            hue_and_lightness(&colors["zstd-1"]),
+            hue_and_lightness(&colors["zstd-19"]),
+        );
+        // two families, half the color wheel apart
+        assert!(
+            (zstd_1.0 - zstd_19.0).abs() < 2.0,
+            "{:?} {:?}",
+            zstd_1,
+            zstd_19
+        );
+        assert!((lz4.0 - zstd_1.0).abs() > 170.0, "{:?} {:?}", lz4, zstd_1);
+        assert!(zstd_1.1 < zstd_19.1);
+
+        let label_options = LabelOptions {
+            family_separator: Some('-'),
+            ..Default::default()
+        };
+        let chart = build_chart(
+            "test",
+            &name_to_benches,
+            &colors,
+            &label_options,
+            GroupBarOptions::default(),
+            &mut Warnings::default(),
+        );
+        let legend: Vec<&str> = chart
+            .legend
+            .iter()
+            .map(|(label, _)| label.as_str())
+            .collect();
+        // a header per family, members without the family prefix. lz4 is alone and a
+        // plain entry
+        assert_eq!(legend, ["lz4", "zstd", "1", "19"]);
+        assert_eq!(chart.legend[0].1, colors["lz4"]);
+        // headers have no color
+        assert_eq!(chart.legend[1].1, "");
+    }
+
+    #[test]
+    fn group_names_compare_by_their_number() {
+        assert_eq!(
+            compare_group_names("bench/2", "bench/1000", false),
+            Ordering::Less
+        );
+        assert_eq!(
+            compare_group_names("bench/1000", "bench/2", false),
+            Ordering::Greater
+        );
+        assert_eq!(
+            compare_group_names("bench/2", "bench/1000", true),
+            Ordering::Greater
+        );
+        // the prefix comes first, also when descending
+        assert_eq!(compare_group_names("a/1000", "b/2", true), Ordering::Less);
+        // leading zeros don't make a number larger, the names still differ
+        assert_eq!(
+            compare_group_names("bench/02", "bench/10", false),
+            Ordering::Less
+        );
+        assert_ne!(
+            compare_group_names("bench/02", "bench/2", false),
+            Ordering::Equal
+        );
+
+        let mut names = vec!["bench/1000", "bench/2", "bench/64", "other"];
+        names.sort_by(|a, b| compare_group_names(a, b, true));
+        assert_eq!(names, ["bench/1000", "bench/64", "bench/2", "other"]);
+    }
+
+    #[test]
+    fn snapped_bars_fill_the_group_width() {
+        let options = GroupBarOptions {
+            snap_bars: true,
+            ..Default::default()
+        };
+        let mut chart = Chart::new("test", options);
+        let variants = ["a", "b", "c", "d", "e", "f"];
+        for idx in 0..10 {
+            let values: Vec<(&str, f32)> = variants.iter().map(|variant| (*variant, 1.0)).collect();
+            chart.add_group(&idx.to_string(), &values);
+        }
+        let layout = ChartLayout::new(&chart.options, &chart.groups);
+        // 70px per group shared by 6 bars
+        assert!(layout.bar_width.fract() != 0.0);
+
+        let svg = chart.render().to_string();
+        let bars: Vec<(f32, f32)> = tags_with(&svg, "height=\"500\"")
+            .iter()
+            .map(|bar| (attribute(bar, "x"), attribute(bar, "width")))
+            .collect();
+        assert_eq!(bars.len(), 60);
+        for (group_idx, group_bars) in bars.chunks(6).enumerate() {
+            assert!(group_bars
+                .iter()
+                .all(|(x, width)| x.fract() == 0.0 && width.fract() == 0.0));
+            let start = layout.bar_x(group_idx, 0);
+            let end = layout.bar_x(group_idx, 5) + layout.bar_width;
+            let (first, last) = (group_bars[0], group_bars[5]);
+            assert_eq!(first.0, start.round());
+            assert_eq!(last.0 + last.1, end.round());
+            let widths: f32 = group_bars.iter().map(|(_, width)| width).sum();
+            assert!((widths - 6.0 * layout.bar_width).abs() <= 1.0, "{}", widths);
+        }
+
+        // the image map has the same edges
+        let html = render_html("test", &svg, std::slice::from_ref(&chart), false, true);
+        for (area, (x, width)) in tags_with(&html, "area ").iter().zip(&bars) {
+            assert!(area.contains(&format!("coords=\"{},10,{},510\"", x, x + width)));
+        }
+    }
+
+    #[test]
+    fn id_allowlist_keeps_only_matching_ids() {
+        assert!(glob_match("Compress/*", "Compress/lz4/1000"));
+        assert!(glob_match("*/lz4/*", "Compress/lz4/1000"));
+        assert!(glob_match("Compress/lz4/1000", "Compress/lz4/1000"));
+        assert!(!glob_match("Compress/lz4", "Compress/lz4/1000"));
+        assert!(!glob_match("*/zstd/*", "Compress/lz4/1000"));
+
+        let list = std::env::temp_dir().join("grouped_bar_chart_id_allowlist.txt");
+        std::fs::write(&list, "# lz4 only\n*/lz4/*\n\nHash/zstd/1000\n").unwrap();
+        let id_allowlist = load_id_patterns(list.to_str().unwrap());
+        assert_eq!(id_allowlist, ["*/lz4/*", "Hash/zstd/1000"]);
+        let lines = [
+            criterion_line("Compress/lz4/1000"),
+            criterion_line("Compress/zstd/1000"),
+            criterion_line("Hash/lz4/1000"),
+            criterion_line("Hash/zstd/1000"),
+            criterion_line("Hash/snappy/1000"),
+        ];
+        let load_options = LoadOptions {
+            id_allowlist,
+            id_denylist: vec!["Hash/lz4/*".to_string()],
+            ..Default::default()
+        };
+        let (name_to_benches, warnings) = load_lines("id_allowlist", &lines, &load_options);
+        let ids: Vec<String> = name_to_benches
+            .values()
+            .flatten()
+            .map(|bench| format!("{}/{}", bench.bench_name, bench.variant))
+            .collect();
+        // the denylist wins over the allowlist
+        assert_eq!(ids, ["Compress/lz4", "Hash/zstd"]);
+        assert!(warnings.entries.is_empty());
+    }
+
+    #[test]
+    fn regressions_depend_on_the_polarity() {
+        let mut chart = Chart::new(
+            "test",
+            GroupBarOptions {
+                regression_threshold: Some(5.0),
+                baseline_variant: Some("old".to_string()),
+                ..Default::default()
+            },
+        );
+        // twice as fast, then twice as slow
+        chart.add_group("faster", &[("old", 1000.0), ("new", 500.0)]);
+        chart.add_group("slower", &[("old", 1000.0), ("new", 2000.0)]);
+        // as throughput, the lower value is worse
+        assert_eq!(
+            chart.regressions(),
+            [("faster".to_string(), "new".to_string(), -50.0)]
+        );
+        // as duration, the higher value is worse
+        chart.options.lower_is_better = true;
+        assert_eq!(
+            chart.regressions(),
+            [("slower".to_string(), "new".to_string(), 100.0)]
+        );
+        let svg = chart.render().to_string();
+        let outlined = tags_with(&svg, "stroke=\"#D62828\"");
+        assert_eq!(outlined.len(), 1);
+        assert_eq!(attribute(outlined[0], "x"), 463.0);
+    }
+
+    #[test]
+    fn boxes_are_outlined_against_the_scaled_baseline() {
+        let mut chart = Chart::new(
+            "test",
+            GroupBarOptions {
+                chart_type: ChartType::Box,
+                regression_threshold: Some(5.0),
+                // e.g. a baseline of 1 scaled to percent of a maximum of 50
+                baseline: 2.0,
+                ..Default::default()
+            },
+        );
+        chart.add_group("a", &[("lz4", 1.0), ("zstd", 3.0)]);
+        chart.groups[0].bars[0].percentiles = Some([0.5, 0.8, 1.0, 1.2, 1.5]);
+        assert_eq!(
+            chart.regressions(),
+            [("a".to_string(), "lz4".to_string(), -50.0)]
+        );
+        let svg = chart.render().to_string();
+        let outlined = tags_with(&svg, "stroke=\"#D62828\"");
+        assert_eq!(outlined.len(), 1);
+        assert!(outlined[0].contains("fill=\"#FFCF56\""));
+    }
+
+    #[test]
+    fn delta_chart_uses_the_scaled_baseline() {
+        // the values of `delta_chart_has_a_bar_per_group` in percent of a maximum of 2
+        let options = GroupBarOptions {
+            delta_chart: true,
+            baseline: 50.0,
+            ..Default::default()
+        };
+        let mut chart = Chart::new("test", options);
+        chart.add_group("a", &[("lz4", 60.0), ("zstd", 50.0)]);
+        chart.add_group("b", &[("lz4", 40.0), ("zstd", 45.0)]);
+        let svg = chart.render().to_string();
+        assert!(svg.contains("+10.00%"));
+        assert!(svg.contains("-15.00%"));
+    }
+
+    #[test]
+    fn percent_of_max_includes_the_p95_of_box_plots() {
+        let mut lz4 = bench("Compress", "lz4", 1000, 2.0);
+        lz4.percentiles = Some([1.0, 1.5, 2.0, 3.0, 4.0]);
+        let benches = group(vec![lz4, bench("Compress", "zstd", 1000, 3.0)]);
+
+        let mut bars = benches.clone();
+        assert_eq!(
+            normalize_to_percent_of_max(&mut bars, ChartType::Bar),
+            Some(3.0)
+        );
+        let mut boxes = benches;
+        assert_eq!(
+            normalize_to_percent_of_max(&mut boxes, ChartType::Box),
+            Some(4.0)
+        );
+        let lz4 = &boxes["Compress/1000"][0];
+        assert_eq!(lz4.gbs, 50.0);
+        assert_eq!(lz4.percentiles, Some([25.0, 37.5, 50.0, 75.0, 100.0]));
+    }
+
+    #[test]
+    fn criterion_throughput_must_fit_into_u32() {
+        let root = std::env::temp_dir().join("grouped_bar_chart_criterion_throughput");
+        let _ = std::fs::remove_dir_all(&root);
+        let write = |name: &str, benchmark: serde_json::Value| {
+            let dir = root.join(name).join("new");
+            std::fs::create_dir_all(&dir).unwrap();
+            let estimates = serde_json::json!({"mean": {"point_estimate": 1000.0}});
+            std::fs::write(dir.join("benchmark.json"), benchmark.to_string()).unwrap();
+            std::fs::write(dir.join("estimates.json"), estimates.to_string()).unwrap();
+            dir
+        };
+        let huge = write(
+            "huge",
+            serde_json::json!({"full_id": "Compress/huge", "throughput": {"Bytes": 1u64 << 32}}),
+        );
+        let mut warnings = Warnings::default();
+        // must not wrap around to 0 bytes
+        assert!(load_criterion_benchmark(&huge, "new", &mut warnings).is_none());
+        assert_eq!(warnings.entries.len(), 1);
+        assert!(warnings.entries[0].message.contains("below 4 GiB"));
+
+        // without a value_str the label is just the function id
+        let plain = write(
+            "plain",
+            serde_json::json!({"full_id": "Compress/lz4", "function_id": "lz4",
+                "throughput": {"Bytes": 1000}}),
+        );
+        let bench = load_criterion_benchmark(&plain, "new", &mut warnings).unwrap();
+        assert_eq!(bench.group_label.as_deref(), Some("lz4"));
+        assert_eq!(bench.num_bytes, 1000);
+    }
+
+    #[test]
+    fn delta_chart_compares_against_the_baseline_variant() {
+        let options = GroupBarOptions {
+            delta_chart: true,
+            baseline_variant: Some("old".to_string()),
+            ..Default::default()
+        };
+        let mut chart = Chart::new("test", options);
+        chart.add_group("a", &[("old", 2.0), ("new", 3.0)]);
+        chart.add_group("b", &[("old", 2.0), ("new", 1.0)]);
+        // only in one run, no delta
+        chart.add_group("c", &[("new", 1.0)]);
+        let svg = chart.render().to_string();
+        assert!(svg.contains("+50.00%"));
+        assert!(svg.contains("-50.00%"));
+        let up = svg.matches("fill=\"#3AB795\"").count();
+        let down = svg.matches("fill=\"#E4572E\"").count();
+        assert_eq!((up, down), (1, 1));
+    }
+
+    #[test]
+    fn duplicate_counters_follow_the_group_order() {
+        // C/10 comes first in the lexical order of the map
+        let name_to_benches = group(vec![bench("C", "lz4", 2, 1.0), bench("C", "lz4", 10, 2.0)]);
+        let label_options = LabelOptions {
+            overrides: BTreeMap::from([
+                ("C/2".to_string(), "same".to_string()),
+                ("C/10".to_string(), "same".to_string()),
+            ]),
+            duplicate_labels: DuplicateLabels::Counter,
+            ..Default::default()
+        };
+        let chart = chart(&name_to_benches, &label_options, GroupBarOptions::default());
+        let groups: Vec<(&str, f32)> = chart
+            .groups
+            .iter()
+            .map(|group| (group.label.as_str(), group.bars[0].value))
+            .collect();
+        assert_eq!(groups, [("same #1", 1.0), ("same #2", 2.0)]);
+    }
+}
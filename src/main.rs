@@ -1,72 +1,42 @@
-use std::collections::{BTreeMap, BTreeSet};
-use std::fmt::Debug;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
-
-use svg::node::element::{self, Group as SVGGroup};
-use svg::node::element::{Line, Rectangle};
-use svg::{Document, Node};
-
-struct BenchData {
-    bench_name: String,
-    group_name: String,
-    variant: String,
-    num_bytes: u32,
-    gbs: f64,
-}
-impl Debug for BenchData {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.debug_struct("BenchData")
-            .field("bench_name", &self.bench_name)
-            .field("group_name", &self.group_name)
-            .field("variant", &self.variant)
-            .field("num_bytes", &self.num_bytes)
-            .field("gbs", &self.gbs)
-            .finish()
-    }
-}
-
-fn load_data(file_name: &str) -> BTreeMap<String, Vec<BenchData>> {
-    let mut groups = BTreeMap::new();
-    //let mut data = Vec::new();
-    //let file = File::open("./data.json").unwrap();
-    let file = File::open(file_name).unwrap();
-    for line in BufReader::new(file).lines() {
-        let line = line.unwrap();
-        let val: serde_json::Value = serde_json::from_str(&line).unwrap();
-        let reason = val["reason"].as_str().unwrap();
-        if reason != "benchmark-complete" {
-            continue;
-        }
-        let name = val["id"].as_str().unwrap().to_string();
-        let components: Vec<String> = name.split("/").map(|el| el.to_string()).collect();
-        let bench_name = components[0].to_string();
-        let variant = components[1].to_string();
-        let num_bytes = components[2].to_string();
-        let duration_ns = val["typical"]["estimate"].as_f64().unwrap();
+use std::path::Path;
+use std::str::FromStr;
 
-        let num_bytes: u32 = num_bytes.parse().unwrap();
+use grouped_bar_chart::*;
 
-        let group_name = format!("{}/{}", bench_name, num_bytes);
+struct Logger;
+impl log::Log for Logger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
 
-        let gbs = num_bytes as f64 / duration_ns;
-        //data.push((bench_name, group_name, variant, num_bytes, gbs));
-        if num_bytes == 96274 {
-            continue;
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        match record.level() {
+            log::Level::Info => eprintln!("{}", record.args()),
+            log::Level::Warn => eprintln!("warning: {}", record.args()),
+            level => eprintln!("{}: {}", level.as_str().to_lowercase(), record.args()),
         }
+    }
 
-        let blub: &mut Vec<_> = groups.entry(group_name.to_string()).or_default();
+    fn flush(&self) {}
+}
 
-        blub.push(BenchData {
-            bench_name,
-            group_name,
-            variant,
-            num_bytes,
-            gbs,
-        });
-    }
-    dbg!(&groups);
-    groups
+static LOGGER: Logger = Logger;
+
+/// Logs errors only with `quiet`, diagnostics with `verbose` and otherwise warnings and the
+/// summary. `quiet` takes precedence.
+fn init_logger(quiet: bool, verbose: bool) {
+    log::set_logger(&LOGGER).unwrap();
+    let level = if quiet {
+        log::LevelFilter::Error
+    } else if verbose {
+        log::LevelFilter::Debug
+    } else {
+        log::LevelFilter::Info
+    };
+    log::set_max_level(level);
 }
 
 use argh::FromArgs;
@@ -76,11 +46,17 @@ use argh::FromArgs;
 struct Arrrrghs {
     /// the filen name of the criterion benches
     #[argh(option, short = 'i')]
-    file_name: String,
+    file_name: Option<String>,
 
-    /// the file name of the of the graph
+    /// the file name of the of the graph. A name ending in .html writes an HTML page
+    /// with the SVG inlined
     #[argh(option, short = 'o')]
-    out: String,
+    out: Option<String>,
+
+    /// print the chart as text bars to stdout. No file is written unless --out is
+    /// given as well
+    #[argh(switch)]
+    preview: bool,
 
     /// the title of the chart
     #[argh(option, short = 't')]
@@ -89,445 +65,996 @@ struct Arrrrghs {
     /// whether or not to show delta between min and max per group
     #[argh(option, short = 'j', default = "false")]
     show_delta: bool,
+
+    /// print the change of each group versus the group before it, e.g. for charts
+    /// ordered by byte size
+    #[argh(switch)]
+    delta_vs_previous: bool,
+
+    /// where to print the delta: above-max (over the highest bar, the default),
+    /// above-group (centered over the group) or top (centered, at the top of the plot)
+    #[argh(option, default = "DeltaPosition::AboveMax")]
+    delta_position: DeltaPosition,
+
+    /// how to handle groups sharing the same label: bench (append the bench
+    /// name), counter (append a running number) or warn
+    #[argh(option, default = "DuplicateLabels::Bench")]
+    duplicate_labels: DuplicateLabels,
+
+    /// render the chart at multiple sizes, e.g. 400x300,800x600. The size is
+    /// appended to the output file name
+    #[argh(option, from_str_fn(parse_sizes))]
+    sizes: Option<Vec<Size>>,
+
+    /// append the min-max range of each variant across all groups to its legend entry
+    #[argh(switch)]
+    legend_range: bool,
+
+    /// merge all groups whose bars are all below this value into a single "other" group
+    #[argh(option)]
+    other_threshold: Option<f64>,
+
+    /// how values are aggregated into the "other" group: mean, median, min, max or sum
+    #[argh(option, default = "Stat::Mean")]
+    other_stat: Stat,
+
+    /// how variants get their color: ordered (by sorted position) or hash (by their
+    /// name, so a variant keeps its color across datasets)
+    #[argh(option, default = "ColorMode::Ordered")]
+    color_mode: ColorMode,
+
+    /// order groups with the same name prefix by their trailing number descending,
+    /// e.g. bench/1000 before bench/2. By default the lower number comes first
+    #[argh(switch)]
+    sort_groups_numeric_desc: bool,
+
+    /// color variants by their family, the part of the name before this character
+    /// (e.g. zstd for zstd-19 with -), with a shade per member and the legend grouped
+    /// by family. Takes precedence over --color-mode
+    #[argh(option)]
+    variant_family_separator: Option<char>,
+
+    /// don't draw the legend, e.g. for charts with a single variant
+    #[argh(switch)]
+    no_legend: bool,
+
+    /// a file with a variant name per line, ordering the bars of every group and the
+    /// legend. Unlisted variants follow in alphabetical order
+    #[argh(option)]
+    variant_order_file: Option<String>,
+
+    /// the order of the legend entries: name, value-asc or value-desc (by the mean
+    /// value of each variant)
+    #[argh(option, default = "LegendSort::Name")]
+    legend_sort: LegendSort,
+
+    /// outline bars which are more than this many percent worse than the baseline,
+    /// the --baseline-value or the old bench of --compare-dir. A lower throughput is
+    /// worse, for the other metrics of --panels a higher value
+    #[argh(option)]
+    regression_threshold: Option<f32>,
+
+    /// exit with an error listing the regressions of --regression-threshold
+    #[argh(switch)]
+    fail_on_regression: bool,
+
+    /// normalize all values against this constant, e.g. a theoretical maximum
+    #[argh(option, from_str_fn(parse_baseline_value))]
+    baseline_value: Option<f64>,
+
+    /// drop every bar with a value below this, after bucketing and normalization.
+    /// Groups without bars are removed
+    #[argh(option)]
+    min_value: Option<f64>,
+
+    /// show all values as percent of the highest value, on an axis from 0 to 100%.
+    /// Bars are labeled with their percentage
+    #[argh(switch)]
+    percent_of_max: bool,
+
+    /// render the percentage change of every group against the baseline as a
+    /// separate chart below the main chart. Requires --baseline-value, or --compare-dir to
+    /// compare the new against the old run
+    #[argh(switch)]
+    delta_chart: bool,
+
+    /// print nothing but errors
+    #[argh(switch, short = 'q')]
+    quiet: bool,
+
+    /// print diagnostics while parsing and rendering
+    #[argh(switch, short = 'v')]
+    verbose: bool,
+
+    /// draw a closed frame around the plot area instead of only the left and bottom axis
+    #[argh(switch)]
+    plot_frame: bool,
+
+    /// draw a faint vertical line between neighbouring groups
+    #[argh(switch)]
+    group_separators: bool,
+
+    /// let the bars grow from the x axis when the SVG is loaded, using SMIL animations
+    #[argh(switch)]
+    animate: bool,
+
+    /// move fills, strokes and font settings into a style block with classes, so the
+    /// chart can be restyled with CSS
+    #[argh(switch)]
+    css_mode: bool,
+
+    /// draw a rounded box with this color behind the title
+    #[argh(option)]
+    title_background: Option<String>,
+
+    /// render every bench as its own small chart, tiled into a single document
+    #[argh(switch)]
+    contact_sheet: bool,
+
+    /// render a panel per metric, stacked and sharing the x positions of the groups,
+    /// e.g. gbs,time_ns,mem_bytes. gbs is the throughput, time_ns the duration and
+    /// everything else a field path of the input lines. The options for the throughput,
+    /// like --percent-of-max, --overlay-line and the deltas, only apply to the gbs panel
+    #[argh(option, from_str_fn(parse_panels))]
+    panels: Option<Vec<String>>,
+
+    /// the number of columns of the contact sheet
+    #[argh(option, default = "2")]
+    columns: u32,
+
+    /// whether all charts of the contact sheet share the same y scale, otherwise
+    /// each chart is scaled to its own highest bar
+    #[argh(option, default = "false")]
+    shared_y: bool,
+
+    /// bar, or box to draw box plots of the --percentile-fields
+    #[argh(option, default = "ChartType::Bar")]
+    chart_type: ChartType,
+
+    /// the fields in the input holding the durations at the 5th, 25th, 50th, 75th and
+    /// 95th percentile, comma separated
+    #[argh(option, from_str_fn(parse_percentile_fields))]
+    percentile_fields: Option<[String; 5]>,
+
+    /// where to print the value of each bar: inside, above or none
+    #[argh(option, default = "DataLabels::None")]
+    data_labels: DataLabels,
+
+    /// draw a dashed vertical line with a text before a group, e.g.
+    /// "64K Text:L2 cache". Can be repeated
+    #[argh(option, from_str_fn(parse_marker))]
+    marker: Vec<(String, String)>,
+
+    /// how the numbers of the y axis are printed: auto, fixed:<decimals>,
+    /// scientific (e.g. 1.2e6) or compact (e.g. 1.5M)
+    #[argh(option, default = "AxisNumberFormat::Auto")]
+    axis_number_format: AxisNumberFormat,
+
+    /// print the numbers of the y axis with k/M/G suffixes, same as
+    /// --axis-number-format compact
+    #[argh(switch)]
+    tick_compact: bool,
+
+    /// a JSON file with an expected value per group label, drawn as a line across
+    /// the bars. The values are scaled like the bars by --baseline-value and
+    /// --percent-of-max
+    #[argh(option)]
+    overlay_line: Option<String>,
+
+    /// draw only the label of every nth group, starting with the first, for charts
+    /// with many groups
+    #[argh(option, default = "1")]
+    label_every_nth: usize,
+
+    /// abbreviate group and legend labels longer than this by replacing their middle
+    /// with an ellipsis. Tooltips keep the full label
+    #[argh(option)]
+    max_label_length: Option<usize>,
+
+    /// the label of the y axis
+    #[argh(option)]
+    y_label: Option<String>,
+
+    /// a JSON file with presentation defaults for the dataset (title, y_label,
+    /// unit and group labels). Defaults to <file_name>.meta.json if it exists
+    #[argh(option)]
+    meta: Option<String>,
+
+    /// the field in the input holding a text label per bar, e.g. `meta.commit`
+    #[argh(option)]
+    bar_label_field: Option<String>,
+
+    /// the field in the input holding the group label, used instead of the byte size.
+    /// Group labels from --meta still take precedence
+    #[argh(option)]
+    group_label_field: Option<String>,
+
+    /// the field in the input holding the number of bytes the throughput is computed
+    /// from, e.g. `throughput.0.per_iteration`. Defaults to the third component of the id
+    #[argh(option)]
+    bytes_field: Option<String>,
+
+    /// only load lines with this reason, * loads all lines
+    #[argh(option, default = "\"benchmark-complete\".to_string()")]
+    reason_filter: String,
+
+    /// load lines without a reason field instead of skipping them with a warning
+    #[argh(switch)]
+    accept_missing_reason: bool,
+
+    /// a file with a benchmark id per line, only matching benchmarks are loaded.
+    /// Ids may contain * wildcards, e.g. BlockCompress/*
+    #[argh(option)]
+    id_allowlist: Option<String>,
+
+    /// a file with a benchmark id per line, matching benchmarks are skipped. Ids may
+    /// contain * wildcards
+    #[argh(option)]
+    id_denylist: Option<String>,
+
+    /// stop reading the input after this many benchmarks, with a warning
+    #[argh(option)]
+    max_rows: Option<usize>,
+
+    /// label bars with the text from --bar-label-field instead of their value
+    #[argh(switch)]
+    show_bar_labels: bool,
+
+    /// color every bar by its value relative to the highest bar instead of by variant.
+    /// The legend shows the color ramp
+    #[argh(switch)]
+    heat: bool,
+
+    /// the two ends of the --heat color ramp, e.g. #3B4CC0,#B40426 (the default)
+    #[argh(
+        option,
+        default = "default_heat_colors()",
+        from_str_fn(parse_heat_colors)
+    )]
+    heat_colors: (String, String),
+
+    /// round the x and width of bars to whole pixels, so the edges are not blurred by
+    /// anti-aliasing
+    #[argh(switch)]
+    snap_bars: bool,
+
+    /// fill the background with a vertical gradient from the first to the second
+    /// color instead of the solid gray, e.g. #444444,#222222
+    #[argh(option, from_str_fn(parse_background_gradient))]
+    background_gradient: Option<(String, String)>,
+
+    /// the units for byte sizes in group labels: si (1000 based, KB/MB, the
+    /// default) or binary (1024 based, KiB/MiB). The sizes of the bundled lz4
+    /// datasets (725, 34308, 64723, 66675 and 9991663 bytes) keep their names,
+    /// e.g. 10Mb Dickens
+    #[argh(option, default = "ByteUnit::Si")]
+    byte_unit: ByteUnit,
+
+    /// add the number of samples and iterations to the tooltip of each bar
+    #[argh(switch)]
+    rich_tooltips: bool,
+
+    /// mark noisy bars with a dot, those with a coefficient of variation of the
+    /// samples above --cv-threshold
+    #[argh(switch)]
+    show_cv: bool,
+
+    /// the coefficient of variation above which --show-cv marks a bar, e.g. 0.05 for 5%
+    #[argh(option, default = "0.05")]
+    cv_threshold: f32,
+
+    /// draw a marker line across each bar at the throughput of the median run
+    #[argh(switch)]
+    median_marker: bool,
+
+    /// rotate the whole finished chart by 0, 90 or 270 degrees, e.g. for portrait printing
+    #[argh(option, default = "0", from_str_fn(parse_rotation))]
+    rotate: u32,
+
+    /// multiply all stroke widths by this factor, so lines stay visible when the SVG
+    /// is rasterized at a high resolution
+    #[argh(option, default = "1.0", from_str_fn(parse_stroke_scale))]
+    stroke_scale: f32,
+
+    /// collapse byte sizes into buckets: pow2 or linear:<step>
+    #[argh(option)]
+    bucket_bytes: Option<Bucketing>,
+
+    /// how values within a bucket are aggregated: mean, median, min, max or sum
+    #[argh(option, default = "Stat::Mean")]
+    bucket_stat: Stat,
+
+    /// write all warnings to this file, as JSON array of objects with kind,
+    /// message and line
+    #[argh(option)]
+    warnings_json: Option<String>,
+
+    /// add a visually hidden table with all values to the HTML output, for screen readers
+    #[argh(switch)]
+    a11y_table: bool,
+
+    /// embed the chart as an image with an image map of the bars in the HTML
+    /// output, with the group, variant and value as data attributes of each area
+    #[argh(switch)]
+    image_map: bool,
+
+    /// write a JSON array describing every written file, with its dimensions, the size
+    /// from --sizes and the titles of the charts it contains
+    #[argh(option)]
+    emit_manifest: Option<String>,
+
+    /// compare two criterion output directories instead of reading --file-name.
+    /// Pass it twice, first the old and then the new directory
+    #[argh(option)]
+    compare_dir: Vec<String>,
 }
 
-fn main() {
-    let arg: Arrrrghs = argh::from_env();
+fn parse_rotation(value: &str) -> Result<u32, String> {
+    match value {
+        "0" => Ok(0),
+        "90" => Ok(90),
+        "270" => Ok(270),
+        _ => Err(format!(
+            "invalid rotation {:?}, expected 0, 90 or 270",
+            value
+        )),
+    }
+}
 
-    let chart_title = arg.title.unwrap_or_default();
+fn default_heat_colors() -> (String, String) {
+    ("#3B4CC0".to_string(), "#B40426".to_string())
+}
 
-    //let file_name = std::env::args().skip(1).next().unwrap();
-    //let chart_title = std::env::args().skip(2).next().unwrap();
-    let name_to_benches = load_data(&arg.file_name);
-    let variants = name_to_benches
-        .iter()
-        .flat_map(|group| group.1.iter())
-        .map(|b| b.variant.to_string())
-        .collect::<BTreeSet<_>>();
-
-    let mut colors = vec![
-        "#3AB795".to_string(),
-        "#A0E8AF".to_string(),
-        "#86BAA1".to_string(),
-        "#EDEAD0".to_string(),
-        "#FFCF56".to_string(),
-    ];
-
-    let variant_to_color: BTreeMap<String, String> = variants
-        .iter()
-        .map(|variant| (variant.to_string(), colors.pop().unwrap().to_string()))
-        .collect();
-
-    let mut groups = vec![];
-
-    for (_name, group) in name_to_benches.iter() {
-        let values_and_color = group
-            .iter()
-            .map(|run| {
-                (
-                    run.gbs as f32,
-                    variant_to_color.get(&run.variant).unwrap().to_string(),
-                )
-            })
-            .collect();
-        let gruppe = Group {
-            label: num_bytes_to_name(group[0].num_bytes),
-            values_and_color,
-        };
-        groups.push(gruppe);
+/// Parses two comma separated `#RRGGBB` colors.
+fn parse_color_pair(value: &str) -> Option<(String, String)> {
+    let is_color = |color: &str| {
+        color.len() == 7
+            && color.starts_with('#')
+            && color[1..].chars().all(|c| c.is_ascii_hexdigit())
+    };
+    match value.split_once(',') {
+        Some((first, second)) if is_color(first) && is_color(second) => {
+            Some((first.to_string(), second.to_string()))
+        }
+        _ => None,
     }
+}
 
-    let opt = GroupBarOptions {
-        total_width: 800.0,
-        total_height: 600.0,
-        chart_area_to_border_padding: 10.0,
-        group_padding: 20.0,
-        bar_padding: 3.0,
-        print_delta: arg.show_delta,
-    };
+fn parse_heat_colors(value: &str) -> Result<(String, String), String> {
+    parse_color_pair(value).ok_or_else(|| {
+        format!(
+            "invalid heat colors {:?}, expected <cool>,<warm> like #3B4CC0,#B40426",
+            value
+        )
+    })
+}
+
+fn parse_background_gradient(value: &str) -> Result<(String, String), String> {
+    parse_color_pair(value).ok_or_else(|| {
+        format!(
+            "invalid background gradient {:?}, expected <top>,<bottom> like #444444,#222222",
+            value
+        )
+    })
+}
 
-    let mut document = element::Group::new();
-    document = document.set("font-family", "Roboto-Regular,Roboto, sans-serif");
-    document = document.set("fill", "#FFFFFF");
-    let rect = Rectangle::new()
-        .set("width", "100%")
-        .set("height", "100%")
-        .set("fill", "#333333");
+fn parse_marker(value: &str) -> Result<(String, String), String> {
+    match value.split_once(':') {
+        Some((label, text)) if !label.is_empty() => Ok((label.to_string(), text.to_string())),
+        _ => Err(format!(
+            "invalid marker {:?}, expected <group_label>:<text>",
+            value
+        )),
+    }
+}
 
-    document = document.add(rect);
+fn parse_stroke_scale(value: &str) -> Result<f32, String> {
+    match value.parse::<f32>() {
+        Ok(scale) if scale.is_finite() && scale > 0.0 => Ok(scale),
+        _ => Err(format!(
+            "invalid stroke scale {:?}, expected a positive number",
+            value
+        )),
+    }
+}
 
-    let document = render_grouped_bar_chart(&chart_title, document, opt, &groups, variant_to_color);
+fn parse_percentile_fields(value: &str) -> Result<[String; 5], String> {
+    let fields: Vec<String> = value.split(',').map(|field| field.to_string()).collect();
+    fields.try_into().map_err(|_| {
+        format!(
+            "invalid percentile fields {:?}, expected <p5>,<p25>,<p50>,<p75>,<p95>",
+            value
+        )
+    })
+}
 
-    svg::save(arg.out, &Document::new().add(document)).unwrap();
+fn parse_panels(value: &str) -> Result<Vec<String>, String> {
+    let panels: Vec<String> = value.split(',').map(|panel| panel.to_string()).collect();
+    if panels.iter().any(|panel| panel.is_empty()) {
+        return Err(format!(
+            "invalid panels {:?}, expected metrics like gbs,time_ns,mem_bytes",
+            value
+        ));
+    }
+    Ok(panels)
 }
 
-fn num_bytes_to_name(num_bytes: u32) -> String {
-    match num_bytes {
-        725 => "725b Text".to_string(),
-        66675 => "66K JSON".to_string(),
-        64723 => "65K Text".to_string(),
-        9991663 => "10Mb Dickens".to_string(),
-        34308 => "34K Text".to_string(),
-        _ => num_bytes.to_string(),
+fn parse_baseline_value(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(baseline) if baseline.is_finite() && baseline > 0.0 => Ok(baseline),
+        _ => Err(format!(
+            "invalid baseline value {:?}, expected a positive number",
+            value
+        )),
     }
 }
 
-const X_AXIS_SPACE: f32 = 80.0;
-#[derive(Debug)]
-struct GroupBarOptions {
-    total_width: f32,
-    total_height: f32,
-    /// chart padding from border
-    chart_area_to_border_padding: f32,
-    /// padding between groups
-    group_padding: f32,
-    /// padding between bars inside group
-    bar_padding: f32,
-    print_delta: bool,
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Size {
+    width: u32,
+    height: u32,
 }
-impl GroupBarOptions {
-    fn get_available_graph_width(&self) -> f32 {
-        let y_axis_space = 80.0;
-        self.total_width - y_axis_space - self.chart_area_to_border_padding * 2.0
+impl FromStr for Size {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || format!("invalid size {:?}, expected <width>x<height>", s);
+        let (width, height) = s.split_once('x').ok_or_else(err)?;
+        Ok(Size {
+            width: width.trim().parse().map_err(|_| err())?,
+            height: height.trim().parse().map_err(|_| err())?,
+        })
     }
-    fn get_available_graph_height(&self) -> f32 {
-        let x_axis_space = X_AXIS_SPACE;
-        self.total_height - x_axis_space - self.chart_area_to_border_padding * 2.0
+}
+
+fn parse_sizes(value: &str) -> Result<Vec<Size>, String> {
+    value.split(',').map(Size::from_str).collect()
+}
+
+/// Appends the size to the file name, e.g. `chart.svg` becomes `chart_400x300.svg`.
+fn sized_file_name(file_name: &str, size: Size) -> String {
+    let path = Path::new(file_name);
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let mut name = format!("{}_{}x{}", stem, size.width, size.height);
+    if let Some(extension) = path.extension() {
+        name = format!("{}.{}", name, extension.to_string_lossy());
     }
+    path.with_file_name(name).to_string_lossy().to_string()
 }
 
-#[derive(Debug)]
-struct Group {
-    label: String,
-    values_and_color: Vec<(f32, String)>,
+/// Writes to a temporary file next to `path` and renames it into place, so a failed write
+/// leaves an existing file intact and readers never see a partial file.
+fn write_atomic(path: &str, contents: impl AsRef<[u8]>) -> std::io::Result<()> {
+    let path = Path::new(path);
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    let tmp_path = path.with_file_name(format!(".{}.{}.tmp", file_name, std::process::id()));
+    let result = std::fs::write(&tmp_path, contents).and_then(|_| std::fs::rename(&tmp_path, path));
+    if result.is_err() {
+        let _ = std::fs::remove_file(&tmp_path);
+    }
+    result
 }
 
-fn compute_y_for_value(options: &GroupBarOptions, val: f32, max_value: f32) -> f32 {
-    let max_height = options.get_available_graph_height();
-    let bar_start = max_height + options.chart_area_to_border_padding;
-    let height = max_height * (val / max_value);
-    bar_start - height
+/// Writes the collected warnings to `--warnings-json`, if set. Called before every exit after
+/// loading, so that the file also explains why nothing was drawn.
+fn write_warnings_json(path: Option<&str>, warnings: &Warnings) {
+    if let Some(path) = path {
+        let json = serde_json::to_string_pretty(&warnings.to_json()).unwrap();
+        write_atomic(path, json).unwrap();
+    }
 }
 
-fn draw_group(
-    doc: SVGGroup,
-    options: &GroupBarOptions,
-    groups: &Group,
-    group_start_x: f32,
-    bar_width: f32,
-    group_width: f32,
-    bar_padding: f32,
-    max_value: f32,
-) -> SVGGroup {
-    let max_height = options.get_available_graph_height();
-    let bar_start = max_height + options.chart_area_to_border_padding;
-    let mut group = doc;
-    let mut bar_x = group_start_x;
-    for (val, color) in groups.values_and_color.iter() {
-        let height = max_height * (val / max_value);
-        let y = compute_y_for_value(options, *val, max_value);
-        let rect = Rectangle::new()
-            .set("x", bar_x)
-            .set("y", y)
-            .set("width", bar_width)
-            .set("height", height)
-            .set("fill", color.to_string());
-
-        group = group.add(rect);
-        bar_x += (bar_width) + bar_padding;
-    }
-
-    let mut node = svg::node::element::Text::new()
-        .set("text-anchor", "left")
-        .set("x", group_start_x)
-        .set("y", bar_start + 20.0);
-    node.append(svg::node::Text::new(groups.label.to_string()));
-    group = group.add(node);
-
-    if options.print_delta {
-        let min = groups
-            .values_and_color
-            .iter()
-            .map(|el| el.0)
-            .min_by(|a, b| a.partial_cmp(b).unwrap())
-            .unwrap();
+fn main() {
+    let arg: Arrrrghs = argh::from_env();
+    init_logger(arg.quiet, arg.verbose);
 
-        let max = groups
-            .values_and_color
-            .iter()
-            .map(|el| el.0)
-            .max_by(|a, b| a.partial_cmp(b).unwrap())
-            .unwrap();
+    let valid_input = match arg.compare_dir.len() {
+        0 => arg.file_name.is_some(),
+        2 => true,
+        _ => false,
+    };
+    if !valid_input {
+        log::error!("expected --file-name or --compare-dir <old> --compare-dir <new>");
+        std::process::exit(1);
+    }
+    if arg.out.is_none() && !arg.preview {
+        log::error!("expected --out or --preview");
+        std::process::exit(1);
+    }
 
-        let y = compute_y_for_value(options, max, max_value);
-        let mut node = svg::node::element::Text::new()
-            .set("text-anchor", "middle")
-            .set("x", group_start_x + bar_width)
-            .set("y", y - 10.0);
-        node.append(svg::node::Text::new(get_percent_difference(min, max)));
-        group = group.add(node);
+    let meta = match (&arg.meta, &arg.file_name) {
+        (Some(path), _) => load_metadata(path),
+        (None, Some(file_name)) if Path::new(&format!("{}.meta.json", file_name)).exists() => {
+            load_metadata(&format!("{}.meta.json", file_name))
+        }
+        _ => Metadata::default(),
+    };
+    let chart_title = arg
+        .title
+        .clone()
+        .or_else(|| meta.title.clone())
+        .unwrap_or_default();
+    let y_axis_label = arg
+        .y_label
+        .clone()
+        .or_else(|| meta.y_label.clone())
+        .unwrap_or_else(|| "Gb/s".to_string());
+
+    //let file_name = std::env::args().skip(1).next().unwrap();
+    //let chart_title = std::env::args().skip(2).next().unwrap();
+    let mut warnings = Warnings::default();
+    let has_baseline = arg.baseline_value.is_some() || arg.compare_dir.len() == 2;
+    if arg.delta_chart && !has_baseline {
+        warnings.add(
+            "ignored_option",
+            "--delta-chart requires --baseline-value or --compare-dir, skipping delta chart"
+                .to_string(),
+            None,
+        );
+    }
+    if arg.regression_threshold.is_some() && !has_baseline {
+        warnings.add(
+            "ignored_option",
+            "--regression-threshold requires --baseline-value or --compare-dir, ignoring it"
+                .to_string(),
+            None,
+        );
+    }
+    if arg.fail_on_regression && arg.regression_threshold.is_none() {
+        warnings.add(
+            "ignored_option",
+            "--fail-on-regression requires --regression-threshold, ignoring it".to_string(),
+            None,
+        );
+    }
+    if arg.chart_type == ChartType::Box && arg.percentile_fields.is_none() {
+        warnings.add(
+            "ignored_option",
+            "--chart-type box requires --percentile-fields, drawing bars".to_string(),
+            None,
+        );
     }
 
-    group
-}
+    let load_options = LoadOptions {
+        bar_label_field: arg.bar_label_field.clone(),
+        group_label_field: arg.group_label_field.clone(),
+        bytes_field: arg.bytes_field.clone(),
+        percentile_fields: arg.percentile_fields.clone(),
+        reason_filter: arg.reason_filter.clone(),
+        accept_missing_reason: arg.accept_missing_reason,
+        max_rows: arg.max_rows,
+        id_allowlist: arg
+            .id_allowlist
+            .as_ref()
+            .map(|path| load_id_patterns(path))
+            .unwrap_or_default(),
+        metric_fields: arg
+            .panels
+            .iter()
+            .flatten()
+            .filter(|panel| !["gbs", "time_ns"].contains(&panel.as_str()))
+            .cloned()
+            .collect(),
+        id_denylist: arg
+            .id_denylist
+            .as_ref()
+            .map(|path| load_id_patterns(path))
+            .unwrap_or_default(),
+    };
+    let mut name_to_benches = match (&arg.file_name, arg.compare_dir.as_slice()) {
+        (_, [old_dir, new_dir]) => load_criterion_dirs(old_dir, new_dir, &mut warnings),
+        (Some(file_name), _) => load_data(file_name, &load_options, &mut warnings),
+        _ => unreachable!(),
+    };
+    if let Some(bucketing) = arg.bucket_bytes {
+        name_to_benches =
+            bucket_benches(&name_to_benches, bucketing, arg.bucket_stat, arg.byte_unit);
+    }
+    if let Some(baseline) = arg.baseline_value {
+        normalize_to_baseline(&mut name_to_benches, baseline);
+    }
+    let percent_of = arg
+        .percent_of_max
+        .then(|| normalize_to_percent_of_max(&mut name_to_benches, arg.chart_type))
+        .flatten();
+    if let Some(min_value) = arg.min_value {
+        drop_below_min_value(&mut name_to_benches, min_value, &mut warnings);
+    }
+    let other_group = arg
+        .other_threshold
+        .and_then(|threshold| merge_into_other(&mut name_to_benches, threshold, arg.other_stat));
+    let variant_order = arg
+        .variant_order_file
+        .as_ref()
+        .map(|path| load_variant_order(path))
+        .unwrap_or_default();
+    if !variant_order.is_empty() {
+        order_variants(&mut name_to_benches, &variant_order);
+    }
+    if name_to_benches.is_empty() {
+        log::error!("no benchmarks left to draw");
+        write_warnings_json(arg.warnings_json.as_deref(), &warnings);
+        std::process::exit(1);
+    }
 
-fn get_percent_difference(min: f32, max: f32) -> String {
-    let difference = max - min;
-    let percent_difference = (difference / min) * 100.0;
-    format!("+{:.2}%", percent_difference)
-}
+    // All filtering has to happen before this point, so that the legend only contains
+    // variants which are actually drawn.
+    let variant_to_color = match arg.variant_family_separator {
+        Some(separator) => {
+            if arg.color_mode != ColorMode::Ordered {
+                warnings.add(
+                    "ignored_option",
+                    "--color-mode is ignored with --variant-family-separator".to_string(),
+                    None,
+                );
+            }
+            build_family_colors(&name_to_benches, separator)
+        }
+        None => build_variant_to_color(&name_to_benches, arg.color_mode),
+    };
 
-fn render_grouped_bar_chart(
-    title: &str,
-    mut doc: SVGGroup,
-    options: GroupBarOptions,
-    groups: &[Group],
-    variant_to_color: BTreeMap<String, String>,
-) -> SVGGroup {
-    let max_value: f32 = groups
-        .iter()
-        .flat_map(|g| &g.values_and_color)
-        .map(|el| el.0)
-        .max_by(|a, b| a.partial_cmp(b).unwrap())
-        .unwrap();
-
-    let available_graph_space = options.get_available_graph_width();
-    let group_width = available_graph_space / groups.len() as f32;
-
-    let max_num_bars_in_group = groups
-        .iter()
-        .map(|g| g.values_and_color.len())
-        .max()
-        .unwrap();
-    let bar_width = (group_width / max_num_bars_in_group as f32).min(20.0);
-
-    let mut group_start_x = Vec::new();
-    let mut curr_group_x = X_AXIS_SPACE + options.chart_area_to_border_padding;
-
-    doc = draw_y_scale(doc, &options, "Gb/s", curr_group_x, max_value);
-    doc = draw_x_scale(doc, &options, "Gb/s", curr_group_x, max_value);
-
-    for group in groups {
-        doc = draw_group(
-            doc,
-            &options,
-            group,
-            curr_group_x,
-            bar_width,
-            group_width,
-            options.bar_padding,
-            max_value,
+    let opt = GroupBarOptions {
+        print_delta: arg.show_delta,
+        delta_position: arg.delta_position,
+        delta_vs_previous: arg.delta_vs_previous,
+        background_gradient: arg.background_gradient.clone(),
+        snap_bars: arg.snap_bars,
+        regression_threshold: arg.regression_threshold.filter(|_| has_baseline),
+        // the values are only normalized with --baseline-value
+        baseline_variant: match arg.baseline_value {
+            Some(_) => None,
+            None => Some("old".to_string()),
+        },
+        // the normalized baseline of 1 is scaled with the other values
+        baseline: percent_of.map_or(1.0, |max_value| (100.0 / max_value) as f32),
+        delta_chart: arg.delta_chart && has_baseline,
+        plot_frame: arg.plot_frame,
+        group_separators: arg.group_separators,
+        animate: arg.animate,
+        max_label_length: arg.max_label_length,
+        cv_threshold: arg.show_cv.then_some(arg.cv_threshold),
+        title_background: arg.title_background,
+        data_labels: match arg.data_labels {
+            DataLabels::None if arg.percent_of_max => DataLabels::Above,
+            data_labels => data_labels,
+        },
+        show_bar_labels: arg.show_bar_labels,
+        median_marker: arg.median_marker,
+        rotate: arg.rotate,
+        markers: arg.marker.clone(),
+        stroke_scale: arg.stroke_scale,
+        axis_number_format: if arg.tick_compact {
+            AxisNumberFormat::Compact
+        } else {
+            arg.axis_number_format
+        },
+        chart_type: arg.chart_type,
+        label_every_nth: arg.label_every_nth,
+        show_legend: !arg.no_legend,
+        y_axis_label: match arg.baseline_value {
+            _ if arg.percent_of_max => format!("% of max {}", y_axis_label),
+            Some(baseline) => format!("{} / {}", y_axis_label, baseline),
+            None => y_axis_label,
+        },
+        y_max: arg.percent_of_max.then_some(100.0),
+        value_suffix: if arg.percent_of_max { "%" } else { "" }.to_string(),
+        ..Default::default()
+    };
+    let label_options = LabelOptions {
+        overrides: meta.labels,
+        byte_unit: arg.byte_unit,
+        duplicate_labels: arg.duplicate_labels,
+        legend_range: arg.legend_range,
+        rich_tooltips: arg.rich_tooltips,
+        legend_sort: arg.legend_sort,
+        variant_order,
+        family_separator: arg.variant_family_separator,
+        group_numeric_desc: arg.sort_groups_numeric_desc,
+        other_group,
+    };
+
+    let panels = arg.panels.as_ref().filter(|_| !arg.contact_sheet);
+    if arg.panels.is_some() && panels.is_none() {
+        warnings.add(
+            "ignored_option",
+            "--panels is not supported with --contact-sheet, ignoring it".to_string(),
+            None,
         );
-        group_start_x.push(curr_group_x);
-        curr_group_x += group_width;
     }
 
-    // Add legend group
-    let mut legend_group = element::Group::new();
-    legend_group = draw_legend(legend_group, &options, &variant_to_color);
-    legend_group = legend_group.set(
-        "transform",
-        format!(
-            "translate({},{})",
-            options.get_available_graph_width() as u32 - 100,
-            20
-        ),
-    );
-    doc = doc.add(legend_group);
-    //doc = doc.set("transform", "translate(0,50)");
-
-    // Add Title
-    let mut node = svg::node::element::Text::new()
-        .set("text-anchor", "middle")
-        .set("font-weight", "bold")
-        .set(
-            "x",
-            options.chart_area_to_border_padding + options.get_available_graph_width() - 70.0,
-        )
-        .set("y", 0);
-    node.append(svg::node::Text::new(title.to_string()));
-    doc = doc.add(node);
+    let mut charts: Vec<Chart> = if let Some(panels) = panels {
+        panels
+            .iter()
+            .enumerate()
+            .map(|(idx, metric)| {
+                let benches = select_metric(&name_to_benches, metric, &mut warnings);
+                // the first panel carries the title and the legend
+                let mut opt = opt.clone();
+                opt.show_legend &= idx == 0;
+                if metric != "gbs" {
+                    // the throughput specific options don't apply to other metrics
+                    opt.y_axis_label = metric.to_string();
+                    opt.y_max = None;
+                    opt.value_suffix = String::new();
+                    opt.delta_chart = false;
+                    opt.print_delta = false;
+                    // --baseline-value only normalizes the throughput, the old bench of
+                    // --compare-dir is the baseline of every metric
+                    if opt.baseline_variant.is_none() {
+                        opt.regression_threshold = None;
+                    }
+                    // durations and e.g. memory are costs
+                    opt.lower_is_better = true;
+                }
+                let title = if idx == 0 { chart_title.as_str() } else { "" };
+                build_chart(
+                    title,
+                    &benches,
+                    &variant_to_color,
+                    &label_options,
+                    opt,
+                    &mut warnings,
+                )
+            })
+            .collect()
+    } else if arg.contact_sheet {
+        split_by_bench(&name_to_benches)
+            .iter()
+            .map(|(bench_name, benches)| {
+                build_chart(
+                    bench_name,
+                    benches,
+                    &variant_to_color,
+                    &label_options,
+                    opt.clone(),
+                    &mut warnings,
+                )
+            })
+            .collect()
+    } else {
+        vec![build_chart(
+            &chart_title,
+            &name_to_benches,
+            &variant_to_color,
+            &label_options,
+            opt,
+            &mut warnings,
+        )]
+    };
 
-    doc
-}
+    for (label, _) in &arg.marker {
+        let known = charts
+            .iter()
+            .flat_map(|chart| &chart.groups)
+            .any(|group| group.label == *label);
+        if !known {
+            warnings.add(
+                "unknown_marker",
+                format!("no group with the label {:?} for the marker", label),
+                None,
+            );
+        }
+    }
 
-fn draw_legend(
-    mut group: SVGGroup,
-    options: &GroupBarOptions,
-    variant_to_color: &BTreeMap<String, String>,
-) -> SVGGroup {
-    group = group.set("fill", "#000000");
-
-    let legend_padding = 10;
-    let lebend_entry_height = 20;
-    let longest_label = variant_to_color
-        .iter()
-        .map(|(label, _)| label.len())
-        .max()
-        .unwrap();
-
-    let legend_width = longest_label * 9;
-    let legend_height = legend_padding * 2 + variant_to_color.len() * lebend_entry_height;
-    let rect = Rectangle::new()
-        .set("width", legend_width)
-        .set("height", legend_height)
-        .set("fill", "#FFFFFF")
-        .set("stroke", "#121212");
-    group = group.add(rect);
-    let mut variant_y = legend_padding + 5;
-    for (label, color) in variant_to_color {
-        let mut node = svg::node::element::Text::new()
-            .set("font-size", 12)
-            .set("x", 10)
-            .set("y", variant_y + 10);
-        node.append(svg::node::Text::new(label.to_string()));
-        group = group.add(node);
-
-        let rect = Rectangle::new()
-            .set("y", variant_y)
-            .set("x", legend_width - 30)
-            .set("width", 20)
-            .set("height", lebend_entry_height - 10)
-            .set("fill", color.to_string());
-        group = group.add(rect);
-        variant_y += lebend_entry_height;
-    }
-
-    group
-}
+    if let Some(path) = &arg.overlay_line {
+        let mut values = load_overlay_line(path);
+        if let Some(baseline) = arg.baseline_value {
+            for value in values.values_mut() {
+                *value /= baseline;
+            }
+        }
+        if let Some(max_value) = percent_of {
+            for value in values.values_mut() {
+                *value *= 100.0 / max_value;
+            }
+        }
+        for label in values.keys() {
+            let known = charts
+                .iter()
+                .flat_map(|chart| &chart.groups)
+                .any(|group| group.label == *label);
+            if !known {
+                warnings.add(
+                    "unknown_overlay_group",
+                    format!("no group with the label {:?} for the overlay line", label),
+                    None,
+                );
+            }
+        }
+        for (idx, chart) in charts.iter_mut().enumerate() {
+            // only the throughput panel has the unit of the overlay line
+            if panels.is_some_and(|panels| panels[idx] != "gbs") {
+                continue;
+            }
+            chart.set_overlay_line(&values);
+        }
+    }
 
-fn draw_y_scale(
-    mut group: SVGGroup,
-    options: &GroupBarOptions,
-    axis_label: &str,
-    group_start_x: f32,
-    max_value: f32,
-) -> SVGGroup {
-    let num_markings = 8;
-
-    let axis_x_pos = group_start_x - 5.0;
-
-    let axis = Line::new()
-        .set("x1", axis_x_pos)
-        .set("y1", options.chart_area_to_border_padding)
-        .set("x2", axis_x_pos)
-        .set(
-            "y2",
-            options.chart_area_to_border_padding + options.get_available_graph_height(),
-        )
-        //.set("width", bar_width)
-        .set("stroke", "#000000".to_string());
-
-    // Add ticks
-    let ticks = bar_axis_ticks(max_value, num_markings);
-    for val in ticks {
-        let y = compute_y_for_value(options, val, max_value);
-        let tick_line = Line::new()
-            .set("x1", axis_x_pos)
-            .set("y1", y)
-            .set("x2", axis_x_pos - 5.0)
-            .set("y2", y)
-            .set("stroke", "#000000".to_string());
-        group = group.add(tick_line);
-
-        // Add grid
-        let tick_line = Line::new()
-            .set("x1", axis_x_pos - 5.0)
-            .set("y1", y)
-            .set(
-                "x2",
-                options.bar_padding + options.get_available_graph_width(),
-            )
-            .set("y2", y)
-            .set("stroke", "#999999".to_string());
-        group = group.add(tick_line);
-
-        let mut node = svg::node::element::Text::new()
-            .set("font-size", 12)
-            .set("direction", "rtl")
-            //.set("text-anchor", "right")
-            .set("x", axis_x_pos - 10.0)
-            .set("y", y + 4.0);
-        node.append(svg::node::Text::new(val.to_string()));
-        group = group.add(node);
-    }
-
-    let mid_point =
-        (options.chart_area_to_border_padding + options.get_available_graph_height()) / 2.0;
-    let mut node = svg::node::element::Text::new()
-        .set("text-anchor", "middle")
-        .set("x", 30)
-        .set("y", mid_point);
-    node.append(svg::node::Text::new(axis_label.to_string()));
-    group = group.add(node);
-
-    group = group.add(axis);
-
-    group
-}
+    if arg.shared_y && arg.contact_sheet {
+        let groups: Vec<Group> = charts
+            .iter()
+            .flat_map(|chart| chart.groups.clone())
+            .collect();
+        let y_max = max_chart_value(&groups, charts[0].options.chart_type);
+        for chart in charts.iter_mut() {
+            chart.options.y_max = Some(y_max);
+        }
+    }
 
-fn bar_axis_ticks(max: f32, num_ticks: usize) -> Vec<f32> {
-    let step_size = calc_step_size(max as f64, num_ticks as f64) as f32;
-    let mut ticks = Vec::with_capacity(num_ticks);
-    for i in 0..num_ticks {
-        ticks.push(i as f32 * step_size);
+    if arg.heat {
+        for chart in charts.iter_mut() {
+            chart.apply_heat(&arg.heat_colors.0, &arg.heat_colors.1);
+        }
     }
 
-    ticks
-}
+    let render = |charts: &[Chart]| {
+        if arg.contact_sheet {
+            render_contact_sheet(charts, arg.columns)
+        } else if panels.is_some() {
+            render_panels(charts)
+        } else {
+            charts[0].render()
+        }
+    };
 
-fn calc_step_size(max_val: f64, target_steps: f64) -> f64 {
-    // calculate an initial guess at step size
-    let temp_step = max_val / target_steps;
+    let image_map = arg.image_map && !arg.contact_sheet && arg.rotate == 0;
+    if arg.image_map && !image_map {
+        warnings.add(
+            "ignored_option",
+            "--image-map is not supported with --contact-sheet or --rotate, ignoring it"
+                .to_string(),
+            None,
+        );
+    }
 
-    // get the magnitude of the step size
-    let mag = f64::floor(f64::ln(temp_step) / std::f64::consts::LN_10);
-    let mag_pow = f64::powi(10.0, mag as i32);
+    let save = |out: &str, document: &svg::Document, charts: &[Chart]| {
+        let mut svg = document.to_string();
+        if arg.css_mode {
+            svg = move_styles_to_css(&svg);
+        }
+        if out.ends_with(".html") {
+            let html = render_html(&chart_title, &svg, charts, arg.a11y_table, image_map);
+            write_atomic(out, html).unwrap();
+        } else {
+            write_atomic(out, svg).unwrap();
+        }
+    };
 
-    // calculate most significant digit of the new step size
-    let mag_msd = f64::round(temp_step / mag_pow + 0.5);
+    if arg.preview {
+        let width = std::env::var("COLUMNS")
+            .ok()
+            .and_then(|columns| columns.parse().ok())
+            .unwrap_or(80);
+        for chart in &charts {
+            print!("{}", render_preview(chart, width));
+        }
+    }
 
-    // promote the MSD to either 1, 2, or 5
-    let mag_msd = if mag_msd > 5.0 {
-        10.0
-    } else if mag_msd > 2.0 {
-        5.0
-    } else if mag_msd > 1.0 {
-        2.0
-    } else {
-        1.0
-    };
+    let mut manifest = vec![];
+    let mut add_to_manifest =
+        |out: &str, document: &svg::Document, charts: &[Chart], size: Option<Size>| {
+            let attribute = |name: &str| -> f64 {
+                document.get_attributes()[name]
+                    .to_string()
+                    .parse()
+                    .unwrap_or_default()
+            };
+            manifest.push(serde_json::json!({
+                "file": out,
+                "width": attribute("width"),
+                "height": attribute("height"),
+                "size": size.map(|size| format!("{}x{}", size.width, size.height)),
+                "charts": charts.iter().map(|chart| chart.title.to_string()).collect::<Vec<_>>(),
+            }));
+        };
+
+    if let Some(out) = &arg.out {
+        let mut out_files = vec![];
+        match &arg.sizes {
+            Some(sizes) => {
+                for size in sizes {
+                    let mut charts = charts.clone();
+                    for chart in charts.iter_mut() {
+                        chart.options.total_width = size.width as f32;
+                        chart.options.total_height = size.height as f32;
+                    }
+                    let document = render(&charts);
+                    let out = sized_file_name(out, *size);
+                    log::debug!("writing {}", out);
+                    save(&out, &document, &charts);
+                    add_to_manifest(&out, &document, &charts, Some(*size));
+                    out_files.push(out);
+                }
+            }
+            None => {
+                let document = render(&charts);
+                save(out, &document, &charts);
+                add_to_manifest(out, &document, &charts, None);
+                out_files.push(out.to_string());
+            }
+        }
+        log::info!(
+            "rendered {} groups with {} variants to {}",
+            charts.iter().map(|chart| chart.groups.len()).sum::<usize>(),
+            variant_to_color.len(),
+            out_files.join(", ")
+        );
+    }
 
-    mag_msd * mag_pow
+    if let Some(path) = &arg.emit_manifest {
+        let json = serde_json::to_string_pretty(&manifest).unwrap();
+        write_atomic(path, json).unwrap();
+    }
+
+    write_warnings_json(arg.warnings_json.as_deref(), &warnings);
+
+    if arg.fail_on_regression {
+        let regressions: Vec<_> = charts
+            .iter()
+            .enumerate()
+            .flat_map(|(idx, chart)| {
+                // the same bench can regress in several panels
+                let metric = panels.map(|panels| format!("{} ", panels[idx]));
+                chart
+                    .regressions()
+                    .into_iter()
+                    .map(move |regression| (metric.clone().unwrap_or_default(), regression))
+            })
+            .collect();
+        if !regressions.is_empty() {
+            for (metric, (group, variant, change)) in &regressions {
+                log::error!(
+                    "regression: {}{} {} {:+.2}%",
+                    metric,
+                    variant,
+                    group,
+                    change
+                );
+            }
+            log::error!(
+                "{} regressions above the threshold of {}%",
+                regressions.len(),
+                arg.regression_threshold.unwrap_or_default()
+            );
+            std::process::exit(1);
+        }
+    }
 }
 
-fn draw_x_scale(
-    mut group: SVGGroup,
-    options: &GroupBarOptions,
-    axis_label: &str,
-    group_start_x: f32,
-    max_value: f32,
-) -> SVGGroup {
-    let num_markings = 4;
-
-    let marking_distance = max_value / 4.0;
-    //let marking_vals = (1..=num_markings).map(||{
-
-    let rect = Line::new()
-        .set("x1", group_start_x - 5.0)
-        .set(
-            "y1",
-            options.chart_area_to_border_padding + options.get_available_graph_height(),
-        )
-        .set("x2", group_start_x + options.get_available_graph_width())
-        .set(
-            "y2",
-            options.chart_area_to_border_padding + options.get_available_graph_height(),
-        )
-        //.set("width", bar_width)
-        .set("stroke", "#000000".to_string());
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    group = group.add(rect);
+    #[test]
+    fn sized_file_name_appends_the_size() {
+        let size = Size {
+            width: 400,
+            height: 300,
+        };
+        assert_eq!(sized_file_name("chart.svg", size), "chart_400x300.svg");
+        assert_eq!(sized_file_name("out/chart", size), "out/chart_400x300");
+    }
 
-    group
+    #[test]
+    fn failed_atomic_write_keeps_the_existing_file() {
+        let dir = std::env::temp_dir().join("grouped_bar_chart_write_atomic");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("chart.svg");
+        let path_str = path.to_str().unwrap();
+        write_atomic(path_str, "old").unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "old");
+
+        // a directory in place of the temporary file makes the write fail
+        let tmp_path = dir.join(format!(".chart.svg.{}.tmp", std::process::id()));
+        std::fs::create_dir(&tmp_path).unwrap();
+        assert!(write_atomic(path_str, "new").is_err());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "old");
+
+        std::fs::remove_dir(&tmp_path).unwrap();
+        write_atomic(path_str, "new").unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "new");
+        // no temporary file is left behind
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+    }
 }
@@ -0,0 +1,539 @@
+//! Runs the binary on the bundled `data.json`.
+
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+fn run(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_grouped_bar_chart"))
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+/// An empty directory for the output of a test.
+fn out_dir(test_name: &str) -> PathBuf {
+    let dir = std::env::temp_dir()
+        .join("grouped_bar_chart_cli")
+        .join(test_name);
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn path(dir: &std::path::Path, file_name: &str) -> String {
+    dir.join(file_name).to_string_lossy().to_string()
+}
+
+/// The value of an attribute of the first `<svg>` tag.
+fn svg_attribute(svg: &str, name: &str) -> String {
+    let tag = &svg[svg.find("<svg").unwrap()..];
+    let tag = &tag[..tag.find('>').unwrap()];
+    let start = tag.find(&format!(" {}=\"", name)).unwrap() + name.len() + 3;
+    tag[start..start + tag[start..].find('"').unwrap()].to_string()
+}
+
+#[test]
+fn sizes_write_a_file_per_size() {
+    let dir = out_dir("sizes");
+    let output = run(&[
+        "-i",
+        "data.json",
+        "-o",
+        &path(&dir, "chart.svg"),
+        "--sizes",
+        "400x300,800x600,1200x900",
+    ]);
+    assert!(output.status.success());
+    for (width, height) in [(400, 300), (800, 600), (1200, 900)] {
+        let svg =
+            std::fs::read_to_string(dir.join(format!("chart_{}x{}.svg", width, height))).unwrap();
+        assert_eq!(svg_attribute(&svg, "width"), width.to_string());
+        assert_eq!(svg_attribute(&svg, "height"), height.to_string());
+        assert_eq!(
+            svg_attribute(&svg, "viewBox"),
+            format!("0 0 {} {}", width, height)
+        );
+    }
+}
+
+#[test]
+fn quiet_prints_nothing_on_success() {
+    let dir = out_dir("quiet");
+    let output = run(&["-i", "data.json", "-o", &path(&dir, "chart.svg"), "-q"]);
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+    assert!(output.stderr.is_empty());
+
+    let output = run(&["-i", "data.json", "-o", &path(&dir, "chart.svg")]);
+    assert!(!output.stderr.is_empty() || !output.stdout.is_empty());
+}
+
+#[test]
+fn meta_sets_title_and_y_label() {
+    let dir = out_dir("meta");
+    let meta = path(&dir, "data.meta.json");
+    std::fs::write(
+        &meta,
+        r#"{"title": "LZ4 Throughput", "unit": "GB/s", "labels": {"725": "tiny"}}"#,
+    )
+    .unwrap();
+    let out = path(&dir, "chart.svg");
+    let output = run(&["-i", "data.json", "-o", &out, "--meta", &meta]);
+    assert!(output.status.success());
+    let svg = std::fs::read_to_string(&out).unwrap();
+    assert!(svg.contains("LZ4 Throughput"));
+    assert!(svg.contains("GB/s"));
+    assert!(svg.contains("tiny"));
+
+    // the command line takes precedence
+    let output = run(&[
+        "-i",
+        "data.json",
+        "-o",
+        &out,
+        "--meta",
+        &meta,
+        "-t",
+        "Other",
+        "--y-label",
+        "MB/s",
+    ]);
+    assert!(output.status.success());
+    let svg = std::fs::read_to_string(&out).unwrap();
+    assert!(!svg.contains("LZ4 Throughput"));
+    assert!(svg.contains("Other"));
+    assert!(svg.contains("MB/s"));
+}
+
+#[test]
+fn warnings_json_lists_skipped_rows_even_when_nothing_is_drawn() {
+    let dir = out_dir("warnings_json");
+    let input = path(&dir, "input.json");
+    std::fs::write(&input, "not json\n{\"reason\":\"benchmark-complete\"}\n").unwrap();
+    let warnings_json = path(&dir, "warnings.json");
+    let output = run(&[
+        "-i",
+        &input,
+        "-o",
+        &path(&dir, "out.svg"),
+        "--warnings-json",
+        &warnings_json,
+    ]);
+    assert!(!output.status.success());
+    let warnings: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&warnings_json).unwrap()).unwrap();
+    let kinds_and_lines: Vec<_> = warnings
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|warning| (warning["kind"].as_str().unwrap(), warning["line"].as_u64()))
+        .collect();
+    assert_eq!(
+        kinds_and_lines,
+        [("invalid_json", Some(1)), ("missing_field", Some(2))]
+    );
+}
+
+#[test]
+fn unknown_marker_warns() {
+    let dir = out_dir("unknown_marker");
+    let warnings_json = path(&dir, "warnings.json");
+    let output = run(&[
+        "-i",
+        "data.json",
+        "-o",
+        &path(&dir, "out.svg"),
+        "--marker",
+        "no such group:cache",
+        "--warnings-json",
+        &warnings_json,
+    ]);
+    assert!(output.status.success());
+    let warnings = std::fs::read_to_string(&warnings_json).unwrap();
+    assert!(warnings.contains("\"unknown_marker\""));
+    let svg = std::fs::read_to_string(dir.join("out.svg")).unwrap();
+    assert!(!svg.contains("stroke-dasharray"));
+}
+
+/// Writes criterion JSON lines with one bench per entry of `benches`, as (id, ns per byte).
+fn write_input(dir: &std::path::Path, benches: &[(&str, f64)]) -> String {
+    let lines: Vec<String> = benches
+        .iter()
+        .map(|(id, ns_per_byte)| {
+            format!(
+                r#"{{"reason":"benchmark-complete","id":"{}","typical":{{"estimate":{}}}}}"#,
+                id,
+                1000.0 * ns_per_byte
+            )
+        })
+        .collect();
+    let input = path(dir, "input.json");
+    std::fs::write(&input, lines.join("\n")).unwrap();
+    input
+}
+
+/// The number of full-height bars of each tile of a contact sheet.
+fn full_height_bars_per_tile(svg: &str) -> Vec<usize> {
+    svg.split("<g transform=\"translate(")
+        .skip(1)
+        .map(|tile| tile.matches("height=\"500\"").count())
+        .collect()
+}
+
+#[test]
+fn contact_sheet_tiles_scale_to_their_own_max() {
+    let dir = out_dir("shared_y");
+    let input = write_input(
+        &dir,
+        &[("Compress/lz4/1000", 1.0), ("Decompress/lz4/1000", 0.25)],
+    );
+    let out = path(&dir, "out.svg");
+    for (shared_y, full_height_bars) in [("false", [1, 1]), ("true", [0, 1])] {
+        let output = run(&[
+            "-i",
+            &input,
+            "-o",
+            &out,
+            "--contact-sheet",
+            "--shared-y",
+            shared_y,
+        ]);
+        assert!(output.status.success());
+        let svg = std::fs::read_to_string(&out).unwrap();
+        assert_eq!(
+            full_height_bars_per_tile(&svg),
+            full_height_bars,
+            "{}",
+            shared_y
+        );
+    }
+}
+
+#[test]
+fn manifest_lists_every_file() {
+    let dir = out_dir("manifest");
+    let manifest = path(&dir, "manifest.json");
+    let chart = path(&dir, "chart.svg");
+    let output = run(&[
+        "-i",
+        "data.json",
+        "-o",
+        &chart,
+        "--sizes",
+        "400x300,800x600",
+        "--emit-manifest",
+        &manifest,
+    ]);
+    assert!(output.status.success());
+    let manifest: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&manifest).unwrap()).unwrap();
+    let entries = manifest.as_array().unwrap();
+    assert_eq!(entries.len(), 2);
+    for (entry, (width, height)) in entries.iter().zip([(400, 300), (800, 600)]) {
+        let file = entry["file"].as_str().unwrap();
+        assert_eq!(file, path(&dir, &format!("chart_{}x{}.svg", width, height)));
+        assert!(std::path::Path::new(file).exists());
+        assert_eq!(entry["width"], width as f64);
+        assert_eq!(entry["height"], height as f64);
+        assert_eq!(entry["size"], format!("{}x{}", width, height));
+    }
+}
+
+#[test]
+fn overlay_line_is_scaled_to_percent_of_max() {
+    let dir = out_dir("overlay_percent_of_max");
+    let input = write_input(
+        &dir,
+        &[("Compress/lz4/1000", 1.0), ("Compress/zstd/1000", 0.5)],
+    );
+    let overlay = path(&dir, "overlay.json");
+    std::fs::write(&overlay, r#"{"1.00 KB": 1.0}"#).unwrap();
+    let out = path(&dir, "out.svg");
+    let output = run(&[
+        "-i",
+        &input,
+        "-o",
+        &out,
+        "--percent-of-max",
+        "--overlay-line",
+        &overlay,
+    ]);
+    assert!(output.status.success());
+    let svg = std::fs::read_to_string(&out).unwrap();
+    // 1 of the max of 2 is 50%, half of the 500px plot above the x axis at 510
+    let polyline = &svg[svg.find("<polyline").unwrap()..];
+    let points = polyline.split("points=\"").nth(1).unwrap();
+    let points = &points[..points.find('"').unwrap()];
+    assert!(points.ends_with(",260"), "{}", points);
+}
+
+/// The tiles of a contact sheet or panel plot.
+fn tiles(svg: &str) -> Vec<&str> {
+    svg.split("<g transform=\"translate(").skip(1).collect()
+}
+
+#[test]
+fn panels_are_aligned_and_only_the_throughput_panel_is_normalized() {
+    let dir = out_dir("panels");
+    let lines: Vec<String> = [("lz4", 1000, 5000), ("zstd", 500, 8000)]
+        .iter()
+        .flat_map(|(variant, duration_ns, mem_bytes)| {
+            [1000, 2000].map(|num_bytes| {
+                format!(
+                    r#"{{"reason":"benchmark-complete","id":"Compress/{}/{}","typical":{{"estimate":{}}},"mem_bytes":{}}}"#,
+                    variant, num_bytes, duration_ns, mem_bytes
+                )
+            })
+        })
+        .collect();
+    let input = path(&dir, "input.json");
+    std::fs::write(&input, lines.join("\n")).unwrap();
+    let overlay = path(&dir, "overlay.json");
+    std::fs::write(&overlay, r#"{"1.00 KB": 1.0, "2.00 KB": 2.0}"#).unwrap();
+    let out = path(&dir, "out.svg");
+    let output = run(&[
+        "-i",
+        &input,
+        "-o",
+        &out,
+        "--panels",
+        "gbs,time_ns,mem_bytes",
+        "--percent-of-max",
+        "--overlay-line",
+        &overlay,
+        "--show-delta",
+        "true",
+    ]);
+    assert!(output.status.success());
+    let svg = std::fs::read_to_string(&out).unwrap();
+    let tiles = tiles(&svg);
+    assert_eq!(tiles.len(), 3);
+    for (tile, y) in tiles.iter().zip([0, 600, 1200]) {
+        assert!(tile.starts_with(&format!("0,{})", y)), "{}", &tile[..20]);
+    }
+    // the groups are at the same x in every panel
+    let group_label_x = |tile: &str| -> Vec<String> {
+        tile.split("text-anchor=\"left\" x=\"")
+            .skip(1)
+            .map(|rest| rest[..rest.find('"').unwrap()].to_string())
+            .collect()
+    };
+    assert_eq!(group_label_x(tiles[0]).len(), 2);
+    assert_eq!(group_label_x(tiles[0]), group_label_x(tiles[1]));
+    assert_eq!(group_label_x(tiles[0]), group_label_x(tiles[2]));
+
+    assert!(tiles[0].contains("100%"));
+    assert!(tiles[0].contains("<polyline"));
+    for tile in &tiles[1..] {
+        assert!(!tile.contains('%'));
+        assert!(!tile.contains("<polyline"));
+    }
+    assert!(tiles[1].contains(">\ntime_ns\n<"));
+    // scaled to the 8000 bytes of zstd
+    assert!(tiles[2].contains(">\nmem_bytes\n<"));
+    assert!(tiles[2].contains(">\n7000\n<"));
+}
+
+#[test]
+fn regressions_are_outlined_and_fail_the_run() {
+    let dir = out_dir("regression");
+    // 1 and 2 GB/s, normalized to 0.5 and 1
+    let input = write_input(
+        &dir,
+        &[("Compress/lz4/1000", 1.0), ("Compress/zstd/1000", 0.5)],
+    );
+    let out = path(&dir, "out.svg");
+    let args = [
+        "-i",
+        &input,
+        "-o",
+        &out,
+        "--baseline-value",
+        "2",
+        "--regression-threshold",
+        "5",
+    ];
+    let output = run(&args);
+    assert!(output.status.success());
+    let svg = std::fs::read_to_string(&out).unwrap();
+    assert_eq!(svg.matches("stroke=\"#D62828\"").count(), 1);
+
+    let output = run(&[&args[..], &["--fail-on-regression"]].concat());
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("regression: lz4 1.00 KB -50.00%"),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn percent_of_max_keeps_the_regressions() {
+    let dir = out_dir("regression_percent_of_max");
+    let input = write_input(
+        &dir,
+        &[("Compress/lz4/1000", 1.0), ("Compress/zstd/1000", 0.5)],
+    );
+    let out = path(&dir, "out.svg");
+    let output = run(&[
+        "-i",
+        &input,
+        "-o",
+        &out,
+        "--baseline-value",
+        "2",
+        "--percent-of-max",
+        "--regression-threshold",
+        "5",
+        "--fail-on-regression",
+    ]);
+    // lz4 is drawn at 50% of zstd, but still half of the baseline
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("regression: lz4 1.00 KB -50.00%"),
+        "{}",
+        stderr
+    );
+    let svg = std::fs::read_to_string(&out).unwrap();
+    assert_eq!(svg.matches("stroke=\"#D62828\"").count(), 1);
+}
+
+/// Writes the `new` directory of a criterion benchmark below `root`.
+fn write_criterion_benchmark(root: &std::path::Path, duration_ns: f64) {
+    let dir = root.join("Compress").join("1000").join("new");
+    std::fs::create_dir_all(&dir).unwrap();
+    let benchmark = r#"{"group_id":"Compress","function_id":"lz4","value_str":"1000",
+        "full_id":"Compress/1000","throughput":{"Bytes":1000}}"#;
+    let estimates = format!(r#"{{"mean":{{"point_estimate":{}}}}}"#, duration_ns);
+    std::fs::write(dir.join("benchmark.json"), benchmark).unwrap();
+    std::fs::write(dir.join("estimates.json"), estimates).unwrap();
+}
+
+#[test]
+fn faster_runs_are_no_regression_in_the_time_panel() {
+    for (name, new_duration_ns, num_regressions) in [("speedup", 500.0, 0), ("slowdown", 2000.0, 2)]
+    {
+        let dir = out_dir(&format!("regression_{}", name));
+        let (old_dir, new_dir) = (dir.join("old"), dir.join("new"));
+        write_criterion_benchmark(&old_dir, 1000.0);
+        write_criterion_benchmark(&new_dir, new_duration_ns);
+        let out = path(&dir, "out.svg");
+        let output = run(&[
+            "--compare-dir",
+            old_dir.to_str().unwrap(),
+            "--compare-dir",
+            new_dir.to_str().unwrap(),
+            "-o",
+            &out,
+            "--panels",
+            "gbs,time_ns",
+            "--regression-threshold",
+            "5",
+            "--fail-on-regression",
+        ]);
+        assert_eq!(output.status.success(), num_regressions == 0, "{}", name);
+        let svg = std::fs::read_to_string(&out).unwrap();
+        // the throughput and the time panel
+        assert_eq!(
+            svg.matches("stroke=\"#D62828\"").count(),
+            num_regressions,
+            "{}",
+            name
+        );
+        // the listing names the panel of each regression
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        for metric in ["gbs", "time_ns"] {
+            assert_eq!(
+                stderr.contains(&format!("regression: {} new lz4", metric)),
+                num_regressions > 0,
+                "{}",
+                stderr
+            );
+        }
+    }
+}
+
+#[test]
+fn percent_of_max_keeps_the_delta_chart() {
+    let dir = out_dir("delta_chart_percent_of_max");
+    let delta_labels = |extra_args: &[&str]| {
+        let out = path(&dir, "out.svg");
+        let args = [
+            "-i",
+            "data.json",
+            "-o",
+            &out,
+            "--delta-chart",
+            "--baseline-value",
+            "5",
+        ];
+        assert!(run(&[&args[..], extra_args].concat()).status.success());
+        let svg = std::fs::read_to_string(&out).unwrap();
+        svg.split('\n')
+            .filter(|line| line.ends_with('%') && line.starts_with(['+', '-']))
+            .map(|line| line.to_string())
+            .collect::<Vec<_>>()
+    };
+    let labels = delta_labels(&[]);
+    assert!(!labels.is_empty());
+    assert_eq!(delta_labels(&["--percent-of-max"]), labels);
+}
+
+#[test]
+fn image_map_covers_every_panel() {
+    let dir = out_dir("image_map_panels");
+    let out = path(&dir, "chart.html");
+    let output = run(&[
+        "-i",
+        "data.json",
+        "-o",
+        &out,
+        "--panels",
+        "gbs,time_ns",
+        "--image-map",
+    ]);
+    assert!(output.status.success());
+    let html = std::fs::read_to_string(&out).unwrap();
+    let bottoms: Vec<&str> = html
+        .split("<area ")
+        .skip(1)
+        .map(|area| {
+            let coords = &area[area.find("coords=\"").unwrap() + 8..];
+            coords[..coords.find('"').unwrap()]
+                .rsplit(',')
+                .next()
+                .unwrap()
+        })
+        .collect();
+    // the bars of data.json in both panels, the second one a tile of 600px below
+    assert_eq!(bottoms.len(), 20);
+    assert!(bottoms[..10].iter().all(|bottom| *bottom == "510"));
+    assert!(bottoms[10..].iter().all(|bottom| *bottom == "1110"));
+}
+
+#[test]
+fn delta_chart_compares_the_new_against_the_old_run() {
+    let dir = out_dir("delta_chart_compare_dir");
+    let (old_dir, new_dir) = (dir.join("old"), dir.join("new"));
+    write_criterion_benchmark(&old_dir, 1000.0);
+    write_criterion_benchmark(&new_dir, 500.0);
+    let out = path(&dir, "out.svg");
+    let output = run(&[
+        "--compare-dir",
+        old_dir.to_str().unwrap(),
+        "--compare-dir",
+        new_dir.to_str().unwrap(),
+        "-o",
+        &out,
+        "--delta-chart",
+    ]);
+    assert!(output.status.success());
+    assert!(!String::from_utf8_lossy(&output.stderr).contains("--delta-chart"));
+    let svg = std::fs::read_to_string(&out).unwrap();
+    // twice the throughput
+    assert!(svg.contains("\n+100.00%\n"));
+    assert_eq!(svg_attribute(&svg, "height"), "760");
+}